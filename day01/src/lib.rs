@@ -1,23 +1,11 @@
-use core::panic;
-use std::{
-    collections::HashMap,
-    fs,
-    io::{self, BufRead},
-    path::Path,
-};
+use std::{collections::HashMap, io::BufRead};
+
+use util::read_file;
 
 #[allow(dead_code)]
 const SAMPLE: &str = "src/sample.txt";
 const INPUT: &str = "src/input.txt";
 
-pub fn read_file(path: &str) -> io::BufReader<fs::File> {
-    let file_path = Path::new(&path);
-    let file = fs::File::open(file_path)
-        .unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e));
-
-    io::BufReader::new(file)
-}
-
 fn parse_location(line: &str) -> (usize, usize) {
     let mut locations = line.split_whitespace();
     let location_one = locations.next().unwrap().parse::<usize>().unwrap();
@@ -1,21 +1,11 @@
-use std::{
-    fs,
-    io::{self, BufRead},
-    path::Path,
-};
+use std::io::BufRead;
+
+use util::read_file;
 
 #[allow(dead_code)]
 const SAMPLE: &str = "src/sample.txt";
 const INPUT: &str = "src/input.txt";
 
-pub fn read_file(path: &str) -> io::BufReader<fs::File> {
-    let file_path = Path::new(&path);
-    let file = fs::File::open(file_path)
-        .unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e));
-
-    io::BufReader::new(file)
-}
-
 pub fn read_reports() -> Vec<std::vec::Vec<i32>> {
     read_file(INPUT)
         .lines()
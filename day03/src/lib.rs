@@ -1,23 +1,11 @@
 use core::str;
-use std::{
-    fs::{self},
-    io::{self, BufRead},
-    path::Path,
-    result::Result,
-};
+use std::{io::BufRead, result::Result};
 
 use regex::{Error, Regex};
+use util::read_file;
 
 const INPUT_PATH: &str = "src/input.txt";
 
-pub fn read_file(path: &str) -> io::BufReader<fs::File> {
-    let file_path = Path::new(&path);
-    let file = fs::File::open(file_path)
-        .unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e));
-
-    io::BufReader::new(file)
-}
-
 pub fn extract(re: &regex::Regex, haystack: &str) -> Vec<(usize, usize)> {
     re.captures_iter(haystack)
         .map(|c| {
@@ -70,13 +58,13 @@ impl Instruction {
     /// ```rust
     /// use day03::Instruction;
     ///
-    /// let instr = Instruction::new("mul(2,4)").unwrap();
+    /// let instr = Instruction::parse("mul(2,4)").unwrap();
     /// assert_eq!(instr, Instruction::Mul(2, 4));
     ///
-    /// let instr = Instruction::new("do()").unwrap();
+    /// let instr = Instruction::parse("do()").unwrap();
     /// assert_eq!(instr, Instruction::Do);
     ///
-    /// let instr = Instruction::new("don't()").unwrap();
+    /// let instr = Instruction::parse("don't()").unwrap();
     /// assert_eq!(instr, Instruction::Dont);
     /// ```
     pub fn parse(input: &str) -> Result<Self, regex::Error> {
@@ -121,7 +109,7 @@ impl Instruction {
     /// use day03::Instruction;
     ///
     /// let input = "mul(2,4)_mul(3,7)&don't()_mul(5,5)_do()_mul(8,5)";
-    /// let instructions = Instruction::extract(input);
+    /// let instructions = Instruction::extract_all(input);
     ///
     /// let parsed: Vec<Instruction> = instructions.into_iter().filter_map(Result::ok).collect();
     /// assert_eq!(parsed, vec![
@@ -171,7 +159,7 @@ impl Instruction {
     ///     Instruction::Mul(8, 5)
     /// ];
     ///
-    /// let result = Instruction::clean(instructions);
+    /// let result = Instruction::filter_enabled(instructions);
     /// assert_eq!(result, vec![(2, 4), (8, 5)]);
     /// ```
     pub fn filter_enabled(instructions: Vec<Instruction>) -> Vec<(usize, usize)> {
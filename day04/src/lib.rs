@@ -1,15 +1,124 @@
-use std::{
-    fs,
-    io::{self, BufRead},
-    path::Path,
-};
-
-pub fn read_file(path: &str) -> io::BufReader<fs::File> {
-    let file_path = Path::new(&path);
-    let file = fs::File::open(file_path)
-        .unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e));
-
-    io::BufReader::new(file)
+use std::{collections::HashSet, io::BufRead};
+
+use util::read_file;
+
+/// The eight directions a word can be read in, as `(row_step, col_step)`.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (0, 1),
+    (0, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (1, 1),
+    (1, -1),
+    (-1, -1),
+];
+
+/// A word search puzzle over a grid of characters.
+///
+/// By default words must fit entirely within the grid. With [`WordSearch::wrapping`]
+/// the grid behaves like a torus: stepping off one edge continues on the opposite one.
+pub struct WordSearch {
+    grid: Vec<Vec<char>>,
+    height: usize,
+    width: usize,
+    wrap: bool,
+}
+
+impl WordSearch {
+    pub fn new(grid: Vec<Vec<char>>) -> Self {
+        let (height, width) = (grid.len(), grid[0].len());
+        Self {
+            grid,
+            height,
+            width,
+            wrap: false,
+        }
+    }
+
+    /// Enables or disables wrapping around the grid's edges.
+    pub fn wrapping(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Moves `steps` times from `(row, col)` in the given direction.
+    ///
+    /// Returns `None` if the resulting cell falls outside the grid and wrapping is disabled.
+    fn step(
+        &self,
+        (row, col): (usize, usize),
+        (dr, dc): (isize, isize),
+        steps: usize,
+    ) -> Option<(usize, usize)> {
+        let row = row as isize + dr * steps as isize;
+        let col = col as isize + dc * steps as isize;
+
+        if self.wrap {
+            Some((
+                row.rem_euclid(self.height as isize) as usize,
+                col.rem_euclid(self.width as isize) as usize,
+            ))
+        } else if (0..self.height as isize).contains(&row)
+            && (0..self.width as isize).contains(&col)
+        {
+            Some((row as usize, col as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Finds every occurrence of `word` in all eight directions.
+    ///
+    /// Each match is returned as the `(row, col)` cells it passes through, in reading order.
+    /// On small wrapping grids different starts can trace the very same cells, such matches
+    /// are only reported once.
+    pub fn find_word_positions(&self, word: &str) -> Vec<Vec<(usize, usize)>> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut seen = HashSet::new();
+        let mut matches = Vec::new();
+
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.grid[row][col] != chars[0] {
+                    continue;
+                }
+
+                for dir in DIRECTIONS {
+                    let cells: Option<Vec<(usize, usize)>> = (0..chars.len())
+                        .map(|idx| self.step((row, col), dir, idx))
+                        .collect();
+
+                    if let Some(cells) = cells {
+                        let found = cells
+                            .iter()
+                            .zip(&chars)
+                            .all(|(&(r, c), &ch)| self.grid[r][c] == ch);
+
+                        if found && seen.insert(cells.clone()) {
+                            matches.push(cells);
+                        }
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Counts the occurrences of `word` in all eight directions.
+    pub fn count(&self, word: &str) -> usize {
+        self.find_word_positions(word).len()
+    }
+}
+
+/// Returns every `(row, col)` cell that at least one occurrence of `word` passes through.
+pub fn covered_cells(grid: &[Vec<char>], word: &str) -> HashSet<(usize, usize)> {
+    WordSearch::new(grid.to_vec())
+        .find_word_positions(word)
+        .into_iter()
+        .flatten()
+        .collect()
 }
 
 pub fn find_word(grid: Vec<Vec<char>>, word: &str) -> usize {
@@ -157,7 +266,7 @@ pub fn find_mas_x(grid: Vec<Vec<char>>) -> usize {
     count
 }
 
-fn part_two() -> usize {
+pub fn part_two() -> usize {
     let grid: Vec<Vec<char>> = read_file("src/input.txt")
         .lines()
         .map_while(Result::ok)
@@ -195,6 +304,58 @@ MXMXAXMASX";
         assert_eq!(count, 18);
     }
 
+    #[test]
+    fn test_word_search_sample() {
+        let grid: Vec<Vec<char>> = SAMPLE
+            .lines()
+            .map(|line| line.chars().collect::<Vec<char>>())
+            .collect();
+
+        let search = WordSearch::new(grid);
+
+        assert_eq!(search.count("XMAS"), 18);
+    }
+
+    #[test]
+    fn test_word_search_wrap_right_edge() {
+        let grid: Vec<Vec<char>> = "ASXM\n....\n...."
+            .lines()
+            .map(|line| line.chars().collect::<Vec<char>>())
+            .collect();
+
+        assert_eq!(WordSearch::new(grid.clone()).count("XMAS"), 0);
+
+        let search = WordSearch::new(grid).wrapping(true);
+        assert_eq!(
+            search.find_word_positions("XMAS"),
+            vec![vec![(0, 2), (0, 3), (0, 0), (0, 1)]]
+        );
+    }
+
+    #[test]
+    fn test_word_search_wrap_no_double_count() {
+        // On a single row, the diagonals wrap back onto the very same cells.
+        let grid = vec!["XMAS".chars().collect::<Vec<char>>()];
+
+        let search = WordSearch::new(grid).wrapping(true);
+
+        assert_eq!(search.count("XMAS"), 1);
+    }
+
+    #[test]
+    fn test_covered_cells() {
+        let grid: Vec<Vec<char>> = SAMPLE
+            .lines()
+            .map(|line| line.chars().collect::<Vec<char>>())
+            .collect();
+
+        let matches = WordSearch::new(grid.clone()).count("XMAS");
+        let cells = covered_cells(&grid, "XMAS");
+
+        assert!(!cells.is_empty());
+        assert!(cells.len() <= matches * "XMAS".len());
+    }
+
     #[test]
     fn test_part_one() {
         let output = part_one();
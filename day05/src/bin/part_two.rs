@@ -1,28 +1,25 @@
-use std::io::BufRead;
-
-use day05::{
-    read::{get_rules, get_sequences, read_file},
-    Graph,
-};
+use day05::{middle, read::parse_input, Graph};
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file("input/05.txt")
-        .lines()
-        .map_while(Result::ok)
-        .collect::<Vec<String>>()
-        .join("\n");
+    let input = read_input("input/05.txt");
 
-    let (rule_pairs, seqs) = input.split_once("\n\n").unwrap();
-    let rules = get_rules(rule_pairs);
+    let (rules, seqs) = parse_input(&input);
 
     let graph = Graph::new(&rules);
 
-    let output: usize = get_sequences(seqs)
+    let middles: Vec<Option<usize>> = seqs
         .iter()
         .filter(|s| !graph.validate(s))
-        .map(|seq| graph.topological_sort(seq))
-        .map(|s| s[s.len() / 2])
-        .sum();
+        .map(|seq| middle(&graph.fix(seq)))
+        .collect();
+
+    let skipped = middles.iter().filter(|m| m.is_none()).count();
+    if skipped > 0 {
+        eprintln!("Skipped {skipped} even-length sequence(s) without a middle page");
+    }
+
+    let output: usize = middles.into_iter().flatten().sum();
 
-    println!("* Solution: {output} *");
+    print_solution(2, output);
 }
@@ -1,35 +1,91 @@
 pub mod read;
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    hash::Hash,
+};
+
+/// Error returned when the ordering rules for a sequence contain a cycle.
+///
+/// `nodes` lists, in ascending order, the pages whose in-degree never dropped to zero,
+/// i.e. the pages that are part of (or only reachable through) the cycle.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CycleError<T = usize> {
+    pub nodes: Vec<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for CycleError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ordering rules contain a cycle between pages {:?}",
+            self.nodes
+        )
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for CycleError<T> {}
 
 /// Represents a directed graph where each node has dependencies defined by rules.
 ///
 /// The graph is implemented as an adjacency list, where the `rules` field maps
 /// each node to the nodes of pages it depends on. This structure is used to model
 /// page ordering rules for an elf's printing system.
-pub struct Graph {
+///
+/// Edges point from a page to its dependencies: for a rule `a|b`, `rules[b]` contains `a`,
+/// so `a` must be printed before `b`.
+///
+/// Pages are `usize` numbers in the puzzle, but any hashable and ordered type works as a node.
+///
+/// # Examples
+/// ```rust
+/// use day05::Graph;
+///
+/// let mut graph = Graph::default();
+/// graph.add_rule(47, 53);
+/// graph.add_rule(97, 47);
+///
+/// assert!(graph.validate(&[97, 47, 53]));
+/// assert!(!graph.validate(&[47, 97, 53]));
+/// ```
+pub struct Graph<T = usize> {
     /// Adjacency list where keys are nodes and values are sets of dependencies.
     /// For a rule `X|Y`, `rules[Y]` will include `X`, meaning `Y` depends on `X`.
-    rules: HashMap<usize, HashSet<usize>>,
+    rules: HashMap<T, HashSet<T>>,
+}
+
+impl<T> Default for Graph<T> {
+    fn default() -> Self {
+        Self {
+            rules: HashMap::new(),
+        }
+    }
 }
 
-impl Graph {
+impl<T: Hash + Eq + Clone + Ord> Graph<T> {
     /// Constructs a new `Graph` from a list of page ordering rules.
     ///
     /// Each rule `(a, b)` indicates that page `b` depends on page `a`,
     /// i.e., `a` must be printed before `b` if both are part of an update.
-    pub fn new(rules: &[(usize, usize)]) -> Self {
-        let mut graph = HashMap::new();
+    pub fn new(rules: &[(T, T)]) -> Self {
+        let mut graph = Self::default();
 
-        for &(a, b) in rules {
-            // This means that b depends on a.
-            graph.entry(b).or_insert_with(HashSet::new).insert(a);
-            // Ensure a is also a key in the graph (even if it has no dependencies).
-            // This is will be useful for Kahn's Algorithm.
-            graph.entry(a).or_insert_with(HashSet::new);
+        for (a, b) in rules {
+            graph.add_rule(a.clone(), b.clone());
         }
 
-        Self { rules: graph }
+        graph
+    }
+
+    /// Adds a single rule `a|b` to the graph: `a` must be printed before `b`.
+    pub fn add_rule(&mut self, a: T, b: T) {
+        // This means that b depends on a.
+        self.rules.entry(b).or_default().insert(a.clone());
+        // Ensure a is also a key in the graph (even if it has no dependencies).
+        // This is will be useful for Kahn's Algorithm.
+        self.rules.entry(a).or_default();
     }
 
     /// Shrinks the graph to only include nodes and dependencies relevant to a specific sequence.
@@ -38,23 +94,25 @@ impl Graph {
     /// the nodes in the sequence and their dependencies (also restricted to the sequence).
     ///
     /// # Parameters
-    /// - `sequence`: A slice of `usize` representing the pages in the sequence.
+    /// - `sequence`: A slice representing the pages in the sequence.
     ///
     /// # Returns
     /// A new `Graph` containing only the relevant nodes and dependencies.
-    fn shrink(&self, sequence: &[usize]) -> Self {
+    fn shrink(&self, sequence: &[T]) -> Self {
         // Create a new adjacency list containing only the nodes in the provided sequence.
         let mut shrunk_graph = HashMap::new();
 
-        for &page in sequence {
-            if let Some(deps) = self.rules.get(&page) {
-                let filtered_deps: HashSet<usize> = deps
-                    .iter()
-                    .filter(|&&dep| sequence.contains(&dep))
-                    .copied()
-                    .collect();
-                shrunk_graph.insert(page, filtered_deps);
-            }
+        for page in sequence {
+            // Pages without any rule are kept too, they just have no dependencies.
+            let filtered_deps: HashSet<T> = self
+                .rules
+                .get(page)
+                .into_iter()
+                .flatten()
+                .filter(|&dep| sequence.contains(dep))
+                .cloned()
+                .collect();
+            shrunk_graph.insert(page.clone(), filtered_deps);
         }
 
         Self {
@@ -66,13 +124,16 @@ impl Graph {
     ///
     /// The output lists each node followed by its dependencies, sorted for readability.
     /// Example output:
-    /// ```
+    /// ```text
     /// 13 -> []
     /// 29 -> [13]
     /// 47 -> [13, 29, 53, 61]
     /// ```
-    pub fn dispaly(&self) {
-        let mut keys = self.rules.keys().collect::<Vec<&usize>>();
+    pub fn dispaly(&self)
+    where
+        T: fmt::Debug,
+    {
+        let mut keys = self.rules.keys().collect::<Vec<&T>>();
         keys.sort();
         for key in keys {
             let mut sorted_key = self
@@ -81,10 +142,10 @@ impl Graph {
                 .unwrap()
                 .iter()
                 .cloned()
-                .collect::<Vec<usize>>();
+                .collect::<Vec<T>>();
 
             sorted_key.sort();
-            println!("{} -> {:?}", key, sorted_key);
+            println!("{:?} -> {:?}", key, sorted_key);
         }
     }
 
@@ -94,11 +155,11 @@ impl Graph {
     /// the dependencies appear before the page in the sequence.
     ///
     /// # Parameters
-    /// - `sequence`: A slice of `usize` representing the sequence to validate.
+    /// - `sequence`: A slice representing the sequence to validate.
     ///
     /// # Returns
     /// `true` if the sequence respects all ordering rules; `false` otherwise.
-    pub fn validate(&self, sequence: &[usize]) -> bool {
+    pub fn validate(&self, sequence: &[T]) -> bool {
         let graph = self.shrink(sequence);
 
         // Store every item's position in sequnce.
@@ -111,6 +172,7 @@ impl Graph {
             if let Some(page_pos) = seq_positions.get(page) {
                 for dep in deps.iter() {
                     if let Some(dep_pos) = seq_positions.get(dep) {
+                        // `dep` must be printed before `page`, so it can't sit after it.
                         if page_pos < dep_pos {
                             return false;
                         }
@@ -121,56 +183,393 @@ impl Graph {
         true
     }
 
+    /// Alias for [`Graph::validate`].
+    #[inline]
+    pub fn is_valid(&self, sequence: &[T]) -> bool {
+        self.validate(sequence)
+    }
+
+    /// Returns `sequence` reordered so that it respects the page ordering rules.
+    ///
+    /// This is a topological sort restricted to the pages of `sequence`, see
+    /// [`Graph::topological_sort`].
+    pub fn fix(&self, sequence: &[T]) -> Vec<T> {
+        self.topological_sort(sequence)
+    }
+
+    /// Returns every page that must be printed before `page`, directly or transitively.
+    ///
+    /// The result never includes `page` itself, even when it sits on a cycle.
+    pub fn transitive_deps(&self, page: &T) -> HashSet<T> {
+        let mut deps = HashSet::new();
+        let mut stack = vec![page];
+
+        while let Some(current) = stack.pop() {
+            for dep in self.rules.get(current).into_iter().flatten() {
+                if dep != page && deps.insert(dep.clone()) {
+                    stack.push(dep);
+                }
+            }
+        }
+
+        deps
+    }
+
+    /// Compares two pages according to the rules between them.
+    ///
+    /// Returns [`Ordering::Less`] if `a` must be printed before `b`, [`Ordering::Greater`] if
+    /// `b` must be printed before `a`, and [`Ordering::Equal`] if no rule relates them.
+    pub fn cmp(&self, a: &T, b: &T) -> Ordering {
+        if self.rules.get(b).is_some_and(|deps| deps.contains(a)) {
+            Ordering::Less
+        } else if self.rules.get(a).is_some_and(|deps| deps.contains(b)) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    /// Sorts `sequence` in place using [`Graph::cmp`].
+    ///
+    /// Only direct rules are consulted, so this agrees with [`Graph::topological_sort`] when
+    /// every pair of pages in the sequence has a rule, as in the puzzle input. The sort is
+    /// stable, so two pages with no rule between them compare equal and keep their relative
+    /// order. Once such gaps break transitivity, `cmp` is no longer a total order and the
+    /// result is unspecified; use [`Graph::fix`] for partial rule sets.
+    pub fn sort_sequence(&self, sequence: &mut [T]) {
+        sequence.sort_by(|a, b| self.cmp(a, b));
+    }
+
     /// Produces a topologically sorted sequence of pages based on the given sequence.
     ///
     /// This method uses Kahn's algorithm to compute a valid ordering of the pages
-    /// while respecting the graph's dependencies.
+    /// while respecting the graph's dependencies. Pages come out in printing order:
+    /// every page appears after all of its dependencies.
     ///
     /// # Parameters
-    /// - `sequence`: A slice of `usize` representing the pages to sort.
+    /// - `sequence`: A slice representing the pages to sort.
+    ///
+    /// # Returns
+    /// A vector representing the topologically sorted sequence.
+    ///
+    /// # Panics
+    /// If the rules restricted to `sequence` contain a cycle. See [`Graph::try_topological_sort`].
+    pub fn topological_sort(&self, sequence: &[T]) -> Vec<T> {
+        match self.try_topological_sort(sequence) {
+            Ok(sorted) => sorted,
+            Err(_) => panic!("ordering rules contain a cycle"),
+        }
+    }
+
+    /// Same as [`Graph::topological_sort`], but reports a cycle instead of returning a
+    /// truncated ordering.
     ///
     /// # Returns
-    /// A vector of `usize` representing the topologically sorted sequence.
-    pub fn topological_sort(&self, sequence: &[usize]) -> Vec<usize> {
+    /// - `Ok(Vec<usize>)`: The topologically sorted sequence.
+    /// - `Err(CycleError)`: If some pages still have dependants once Kahn's algorithm runs
+    ///   out of pages to remove.
+    pub fn try_topological_sort(&self, sequence: &[T]) -> Result<Vec<T>, CycleError<T>> {
         // Shrink the universal graph into one per this sequence.
         let graph = self.shrink(sequence);
 
-        // Computing the degrees for all nodes that exist in the adjacency list.
-        let mut in_degree = HashMap::new();
-        for &node in graph.rules.keys() {
-            in_degree.entry(node).or_insert(0);
-        }
-        for dependencies in graph.rules.values() {
-            for &dep in dependencies {
-                *in_degree.entry(dep).or_insert(0) += 1;
+        // A page's degree is the number of its dependencies not yet printed.
+        // Ordered containers keep the result independent of hashing order.
+        let mut in_degree = BTreeMap::new();
+        let mut dependants: HashMap<&T, Vec<&T>> = HashMap::new();
+        for (page, deps) in graph.rules.iter() {
+            in_degree.insert(page, deps.len());
+            for dep in deps {
+                dependants.entry(dep).or_default().push(page);
             }
         }
 
-        // Init queue with pages that has no deps.
-        let mut queue = VecDeque::new();
+        // Init queue with pages that has no deps. Ties are broken by the smallest page first.
+        let mut queue = BTreeSet::new();
         for (&page, &degree) in in_degree.iter() {
             if degree == 0 {
-                queue.push_back(page);
+                queue.insert(page);
             }
         }
 
         let mut sorted_seq = Vec::new();
-        while let Some(page) = queue.pop_front() {
-            if sequence.contains(&page) {
-                sorted_seq.push(page);
-            }
+        while let Some(page) = queue.pop_first() {
+            sorted_seq.push(page.clone());
 
-            // I want to update degrees based on removed item.
-            for dep in &graph.rules[&page] {
-                if let Some(degree) = in_degree.get_mut(dep) {
+            // Printing `page` satisfies one dependency of every page waiting on it.
+            for &dependant in dependants.get(page).into_iter().flatten() {
+                if let Some(degree) = in_degree.get_mut(dependant) {
                     *degree -= 1;
                     if *degree == 0 {
-                        queue.push_back(*dep);
+                        queue.insert(dependant);
                     }
                 }
             }
         }
 
-        sorted_seq
+        // Any page left with a non-zero degree could never be removed: it's on a cycle.
+        let remaining: Vec<T> = in_degree
+            .into_iter()
+            .filter(|&(_, degree)| degree > 0)
+            .map(|(page, _)| page.clone())
+            .collect();
+
+        if remaining.is_empty() {
+            Ok(sorted_seq)
+        } else {
+            Err(CycleError { nodes: remaining })
+        }
+    }
+}
+
+/// Returns the middle page of a sequence.
+///
+/// Only odd-length sequences have a well defined middle, so `None` is returned for even
+/// lengths (including the empty sequence).
+pub fn middle(sequence: &[usize]) -> Option<usize> {
+    if sequence.len() % 2 == 1 {
+        Some(sequence[sequence.len() / 2])
+    } else {
+        None
+    }
+}
+
+/// Solves both parts from the raw puzzle input, parsing and building the graph only once.
+///
+/// # Returns
+/// A tuple of the sum of middle pages of the valid sequences (part one) and the sum of
+/// middle pages of the invalid sequences once fixed (part two). Even-length sequences have
+/// no middle page and are left out, see [`middle`].
+pub fn solve(input: &str) -> (usize, usize) {
+    let (rules, sequences) = read::parse_input(input);
+    let graph = Graph::new(&rules);
+
+    let (valid, invalid): (Vec<_>, Vec<_>) = sequences.iter().partition(|s| graph.validate(s));
+
+    let part_one = valid.iter().filter_map(|s| middle(s)).sum();
+    let part_two = invalid
+        .iter()
+        .map(|s| graph.fix(s))
+        .filter_map(|s| middle(&s))
+        .sum();
+
+    (part_one, part_two)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read::{parse_input, try_get_rules, try_get_sequences, ParseError};
+
+    const SAMPLE: &str = r"47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47";
+
+    fn sample() -> (Vec<(usize, usize)>, Vec<Vec<usize>>) {
+        parse_input(SAMPLE)
+    }
+
+    #[test]
+    fn test_topological_sort_detects_cycle() {
+        let graph = Graph::new(&[(1, 2), (2, 1)]);
+
+        let result = graph.try_topological_sort(&[1, 2]);
+
+        assert_eq!(result, Err(CycleError { nodes: vec![1, 2] }));
+    }
+
+    #[test]
+    fn test_topological_sort_is_deterministic() {
+        let (rules, sequences) = sample();
+
+        for seq in &sequences {
+            // Separate graphs get separate hash seeds.
+            let first = Graph::new(&rules).topological_sort(seq);
+            let second = Graph::new(&rules).topological_sort(seq);
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn test_graph_add_rule() {
+        let mut graph = Graph::default();
+        graph.add_rule(75, 47);
+        graph.add_rule(47, 61);
+        graph.add_rule(75, 61);
+
+        assert!(graph.validate(&[75, 47, 61]));
+        assert!(!graph.validate(&[61, 47, 75]));
+    }
+
+    #[test]
+    fn test_validate_ordered_sequence() {
+        let (rules, _) = sample();
+        let graph = Graph::new(&rules);
+
+        assert!(graph.validate(&[75, 47, 61, 53, 29]));
+    }
+
+    #[test]
+    fn test_validate_dependency_after_dependent() {
+        let (rules, _) = sample();
+        let graph = Graph::new(&rules);
+
+        // `97|75` requires 97 to come first.
+        assert!(!graph.validate(&[75, 97, 47, 61, 53]));
+    }
+
+    #[test]
+    fn test_part_one_sample() {
+        let (rules, sequences) = sample();
+        let graph = Graph::new(&rules);
+
+        let output: usize = sequences
+            .iter()
+            .filter(|s| graph.validate(s))
+            .map(|s| s[s.len() / 2])
+            .sum();
+
+        assert_eq!(output, 143);
+    }
+
+    #[test]
+    fn test_fix_invalid_sequences() {
+        let (rules, _) = sample();
+        let graph = Graph::new(&rules);
+
+        let cases = [
+            (vec![75, 97, 47, 61, 53], vec![97, 75, 47, 61, 53]),
+            (vec![61, 13, 29], vec![61, 29, 13]),
+            (vec![97, 13, 75, 29, 47], vec![97, 75, 47, 29, 13]),
+        ];
+
+        for (seq, expected) in cases {
+            let fixed = graph.fix(&seq);
+            assert_eq!(fixed, expected);
+            assert!(graph.is_valid(&fixed));
+        }
+    }
+
+    #[test]
+    fn test_graph_with_str_nodes() {
+        let graph = Graph::new(&[("a", "b"), ("b", "c"), ("a", "c")]);
+
+        assert!(graph.validate(&["a", "b", "c"]));
+        assert!(!graph.validate(&["c", "a", "b"]));
+        assert_eq!(graph.fix(&["c", "b", "a"]), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let (rules, sequences) = parse_input(&format!("{SAMPLE}\n"));
+
+        assert_eq!(rules.len(), 21);
+        assert_eq!(sequences.len(), 6);
+    }
+
+    #[test]
+    fn test_try_get_rules_missing_separator() {
+        let result = try_get_rules("47|53\n9713\n97|61");
+
+        assert_eq!(
+            result,
+            Err(ParseError::MissingSeparator {
+                line: "9713".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_get_sequences_invalid_page() {
+        let result = try_get_sequences("75,47,61\n97,x,53");
+
+        assert_eq!(
+            result,
+            Err(ParseError::InvalidPage {
+                line: "97,x,53".to_string(),
+                page: "x".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_sort_sequence_matches_topological_sort() {
+        let (rules, sequences) = sample();
+        let graph = Graph::new(&rules);
+
+        for seq in sequences.iter().filter(|s| !graph.validate(s)) {
+            let mut sorted = seq.clone();
+            graph.sort_sequence(&mut sorted);
+            assert_eq!(sorted, graph.topological_sort(seq));
+        }
+    }
+
+    #[test]
+    fn test_sort_sequence_without_rule_keeps_order() {
+        let graph = Graph::new(&[(1, 2)]);
+
+        assert_eq!(graph.cmp(&3, &4), Ordering::Equal);
+
+        for seq in [vec![3, 4], vec![4, 3]] {
+            let mut sorted = seq.clone();
+            graph.sort_sequence(&mut sorted);
+            assert_eq!(sorted, seq);
+        }
+
+        let mut seq = vec![2, 1];
+        graph.sort_sequence(&mut seq);
+        assert_eq!(seq, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_transitive_deps() {
+        let (rules, _) = sample();
+        let graph = Graph::new(&rules);
+
+        assert_eq!(graph.transitive_deps(&61), HashSet::from([97, 47, 75]));
+        assert_eq!(graph.transitive_deps(&97), HashSet::new());
+    }
+
+    #[test]
+    fn test_transitive_deps_terminates_on_cycle() {
+        let graph = Graph::new(&[(1, 2), (2, 3), (3, 1)]);
+
+        assert_eq!(graph.transitive_deps(&1), HashSet::from([2, 3]));
+    }
+
+    #[test]
+    fn test_solve_sample() {
+        assert_eq!(solve(SAMPLE), (143, 123));
+    }
+
+    #[test]
+    fn test_middle() {
+        assert_eq!(middle(&[75, 47, 61, 53, 29]), Some(61));
+        assert_eq!(middle(&[75, 47, 61, 53]), None);
+        assert_eq!(middle(&[]), None);
     }
 }
@@ -1,31 +1,67 @@
-use std::{fs, io, path::Path};
+use std::fmt;
 
-pub fn read_file(path: &str) -> io::BufReader<fs::File> {
-    let file_path = Path::new(&path);
-    let file = fs::File::open(file_path)
-        .unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e));
+/// Error returned when a line of the puzzle input can't be parsed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// A rule line without the `|` separator.
+    MissingSeparator { line: String },
+    /// A page that isn't a valid number, along with the line it appears on.
+    InvalidPage { line: String, page: String },
+}
 
-    io::BufReader::new(file)
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingSeparator { line } => {
+                write!(f, "rule is missing the `|` separator: {:?}", line)
+            }
+            ParseError::InvalidPage { line, page } => {
+                write!(f, "invalid page {:?} on line {:?}", page, line)
+            }
+        }
+    }
 }
 
-pub fn get_rules(raw: &str) -> Vec<(usize, usize)> {
+impl std::error::Error for ParseError {}
+
+fn parse_page(line: &str, page: &str) -> Result<usize, ParseError> {
+    page.parse::<usize>().map_err(|_| ParseError::InvalidPage {
+        line: line.to_string(),
+        page: page.to_string(),
+    })
+}
+
+/// Parses page ordering rules, one `X|Y` per line.
+pub fn try_get_rules(raw: &str) -> Result<Vec<(usize, usize)>, ParseError> {
     raw.lines()
-        .map(|l| l.split_once("|").unwrap())
-        .map(|(first, second)| {
-            (
-                first.parse::<usize>().unwrap(),
-                second.parse::<usize>().unwrap(),
-            )
+        .map(|l| {
+            let (first, second) = l.split_once("|").ok_or(ParseError::MissingSeparator {
+                line: l.to_string(),
+            })?;
+            Ok((parse_page(l, first)?, parse_page(l, second)?))
         })
         .collect()
 }
 
-pub fn get_sequences(raw: &str) -> Vec<Vec<usize>> {
+/// Parses the sequences of pages to print, one comma separated update per line.
+pub fn try_get_sequences(raw: &str) -> Result<Vec<Vec<usize>>, ParseError> {
     raw.lines()
-        .map(|l| {
-            l.split(",")
-                .map(|p| p.parse::<usize>().unwrap())
-                .collect::<Vec<usize>>()
-        })
+        .map(|l| l.split(",").map(|p| parse_page(l, p)).collect())
         .collect()
 }
+
+pub fn get_rules(raw: &str) -> Vec<(usize, usize)> {
+    try_get_rules(raw).unwrap()
+}
+
+pub fn get_sequences(raw: &str) -> Vec<Vec<usize>> {
+    try_get_sequences(raw).unwrap()
+}
+
+/// Parses the whole puzzle input: the rules block, blank lines, then the sequences block.
+///
+/// Surrounding whitespace, such as a trailing newline after the sequences, is ignored.
+pub fn parse_input(input: &str) -> (Vec<(usize, usize)>, Vec<Vec<usize>>) {
+    let (rules, sequences) = util::split_two_sections(input).unwrap();
+    (get_rules(rules), get_sequences(sequences))
+}
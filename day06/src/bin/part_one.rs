@@ -1,17 +1,10 @@
-use std::io::BufRead;
-
 use day06::Map;
-use util::read_file;
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file("input/06.txt")
-        .lines()
-        .map_while(Result::ok)
-        .map(|l| l.chars().collect())
-        .collect();
-
-    let mut map = Map::new(input);
+    let input = read_input("input/06.txt");
+    let mut map = Map::from(input.as_str());
     map.walk();
     let output = map.count_steps();
-    println!("* Solution: {output} *");
+    print_solution(1, output);
 }
@@ -1,16 +1,9 @@
-use std::io::BufRead;
-
 use day06::Map;
-use util::read_file;
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file("input/06.txt")
-        .lines()
-        .map_while(Result::ok)
-        .map(|l| l.chars().collect())
-        .collect();
-
-    let map = Map::new(input);
-    let output = map.find_traps();
-    println!("** Solution: {output} **");
+    let input = read_input("input/06.txt");
+    let map = Map::from(input.as_str());
+    let output = map.find_traps_parallel();
+    print_solution(2, output);
 }
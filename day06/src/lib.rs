@@ -1,28 +1,18 @@
 #![allow(dead_code)]
 
-use std::{collections::HashSet, fmt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, thread,
+};
 
 /// Represents a 2D coordinate on the map.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct Location {
-    x: usize,
-    y: usize,
+pub struct Location {
+    pub x: usize,
+    pub y: usize,
 }
 
-/// Represents a direction the guard can take.
-///
-/// Directions include:
-/// - Left (`<`)
-/// - Right (`>`)
-/// - Up (`^`)
-/// - Down (`v`)
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-pub enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
-}
+pub use util::Direction;
 
 /// Represents a guard patrolling a map.
 ///
@@ -63,58 +53,39 @@ impl Location {
     }
 }
 
-impl TryFrom<char> for Direction {
-    type Error = String;
-
-    fn try_from(c: char) -> Result<Self, Self::Error> {
-        match c {
-            '<' => Ok(Direction::Left),
-            '>' => Ok(Direction::Right),
-            '^' => Ok(Direction::Up),
-            'v' => Ok(Direction::Down),
-            _ => Err(format!("Invalid character for direction: {}", c)),
-        }
-    }
+/// Explains why a bounded patrol stopped. See [`Map::walk_bounded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The guard walked off the map.
+    LeftMap,
+    /// The guard was still on the map when the step cap was reached.
+    StepCap,
+    /// There's no guard on the map.
+    NoGuard,
 }
 
-impl From<Direction> for char {
-    fn from(val: Direction) -> Self {
-        match val {
-            Direction::Left => '>',
-            Direction::Right => '<',
-            Direction::Up => '^',
-            Direction::Down => 'v',
-        }
-    }
+/// Error returned by [`Map::try_new`] when the map doesn't have exactly one guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapError {
+    /// There's no guard on the map.
+    NoGuard,
+    /// More than one guard, along with how many were found.
+    MultipleGuards(usize),
 }
 
-impl Direction {
-    /// Rotates the direction 90 degrees clockwise.
-    pub(crate) fn rotate(&self) -> Self {
-        match self {
-            Direction::Right => Direction::Down,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-            Direction::Up => Direction::Right,
-        }
-    }
-
-    /// Returns the `(col_step, row_step)` delta for movement in the given direction.
-    fn signum(&self) -> (i8, i8) {
-        let mut row_step = 0;
-        let mut col_step = 0;
-
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Direction::Left => row_step = -1,
-            Direction::Right => row_step = 1,
-            Direction::Up => col_step = -1,
-            Direction::Down => col_step = 1,
+            MapError::NoGuard => write!(f, "no guard found on the map"),
+            MapError::MultipleGuards(n) => {
+                write!(f, "expected a single guard on the map, found {}", n)
+            }
         }
-
-        (col_step, row_step)
     }
 }
 
+impl std::error::Error for MapError {}
+
 /// Represents the lab map containing obstacles, the guard, and dimensions.
 ///
 /// The map tracks:
@@ -152,6 +123,21 @@ impl Map {
         map
     }
 
+    /// Same as [`Map::new`], but rejects maps without exactly one guard.
+    pub fn try_new(data: Vec<Vec<char>>) -> Result<Self, MapError> {
+        let guards = data
+            .iter()
+            .flatten()
+            .filter(|&&c| Direction::try_from(c).is_ok())
+            .count();
+
+        match guards {
+            1 => Ok(Self::new(data)),
+            0 => Err(MapError::NoGuard),
+            n => Err(MapError::MultipleGuards(n)),
+        }
+    }
+
     /// Calculates guard's initial position and direction on a map.
     ///
     /// Scans the map for a character representing the guard's direction (`^`, `<`, `>`, or `v`).
@@ -180,100 +166,254 @@ impl Map {
     ///
     /// The guard follows the patrol protocol:
     /// 1. If there is an obstacle directly ahead, the guard turns 90 degrees right. See
-    ///    [`Direction::rotate`]
+    ///    [`Direction::rotate_cw`]
     /// 2. Otherwise, the guard continues forward in her current direction.
     ///
     /// Marks all positions visited by the guard with `X`.
     pub fn walk(&mut self) {
+        self.walk_bounded(usize::MAX);
+    }
+
+    /// Same as [`Map::walk`], but gives up after `max_steps` steps.
+    ///
+    /// Every move forward and every turn counts as one step.
+    ///
+    /// # Returns
+    /// The [`StopReason`] the patrol ended with.
+    pub fn walk_bounded(&mut self, max_steps: usize) -> StopReason {
+        if self.guard.is_none() {
+            return StopReason::NoGuard;
+        }
+
+        let mut steps = 0;
         while let Some(guard) = self.guard {
-            let (col_step, row_step) = guard.dir.signum();
-
-            // The new location will never be less than zero, otherwise it will be null.
-            if let Some(next_loc) = guard.loc.delta(row_step, col_step) {
-                if next_loc.x < self.width && next_loc.y < self.height {
-                    if self.data[next_loc.y][next_loc.x] == '#' {
-                        self.update_guard(guard.loc, guard.dir.rotate());
-                    } else {
-                        self.data[guard.loc.y][guard.loc.x] = 'X';
-                        self.update_guard(next_loc, guard.dir);
-                    }
-                } else {
+            if steps == max_steps {
+                return StopReason::StepCap;
+            }
+
+            self.step_guard(guard);
+            steps += 1;
+        }
+
+        StopReason::LeftMap
+    }
+
+    /// Moves the guard one step: either forward or a turn in front of an obstacle.
+    fn step_guard(&mut self, guard: Guard) {
+        match self.next_guard(guard) {
+            Some(next) => {
+                if next.loc != guard.loc {
                     self.data[guard.loc.y][guard.loc.x] = 'X';
-                    self.guard = None;
                 }
-            } else {
+                self.update_guard(next.loc, next.dir);
+            }
+            None => {
                 self.data[guard.loc.y][guard.loc.x] = 'X';
                 self.guard = None;
             }
         }
     }
 
-    /// Counts the total number of positions visited by the guard (`X`).
-    pub fn count_steps(&self) -> usize {
-        let mut steps = 0;
+    /// Computes the guard's state after one step, without touching the map.
+    ///
+    /// Returns `None` if the step takes the guard off the map.
+    fn next_guard(&self, guard: Guard) -> Option<Guard> {
+        let (row_step, col_step) = guard.dir.delta();
+
+        // The new location will never be less than zero, otherwise it will be null.
+        let next_loc = guard.loc.delta(col_step, row_step)?;
+        if next_loc.x >= self.width || next_loc.y >= self.height {
+            return None;
+        }
+
+        if self.data[next_loc.y][next_loc.x] == '#' {
+            Some(Guard::new(guard.loc, guard.dir.rotate_cw()))
+        } else {
+            Some(Guard::new(next_loc, guard.dir))
+        }
+    }
+
+    /// Yields every state of the guard's patrol, starting with her current one.
+    ///
+    /// The map is left untouched. The iterator ends once the guard leaves the map, and never
+    /// ends if she's stuck in a loop.
+    pub fn steps(&self) -> impl Iterator<Item = (Location, Direction)> + '_ {
+        std::iter::successors(self.guard, |&guard| self.next_guard(guard))
+            .map(|guard| (guard.loc, guard.dir))
+    }
+
+    /// Returns the distinct `(x, y)` cells the guard steps on during her patrol.
+    ///
+    /// The map is left untouched. If the guard is stuck in a loop, the patrol is followed until
+    /// it starts repeating. Cells already marked by a previous [`Map::walk`] are included as well.
+    pub fn visited(&self) -> HashSet<(usize, usize)> {
+        let mut visited = HashSet::new();
         for col in 0..self.height {
             for row in 0..self.width {
                 if self.data[col][row] == 'X' {
-                    steps += 1;
+                    visited.insert((row, col));
                 }
             }
         }
 
-        steps
+        let mut states = HashSet::new();
+        for (loc, dir) in self.steps() {
+            if !states.insert((loc, dir)) {
+                break;
+            }
+            visited.insert((loc.x, loc.y));
+        }
+
+        visited
+    }
+
+    /// Counts the distinct cells the guard enters more than once during her patrol, in any
+    /// direction. Turning in place doesn't count as entering a cell again.
+    ///
+    /// If the guard is stuck in a loop, the patrol is followed until it starts repeating.
+    pub fn revisit_count(&self) -> usize {
+        let mut entries: HashMap<Location, usize> = HashMap::new();
+        let mut states = HashSet::new();
+        let mut previous = None;
+
+        for (loc, dir) in self.steps() {
+            if !states.insert((loc, dir)) {
+                break;
+            }
+            if previous != Some(loc) {
+                *entries.entry(loc).or_default() += 1;
+            }
+            previous = Some(loc);
+        }
+
+        entries.values().filter(|&&count| count > 1).count()
+    }
+
+    /// Counts the total number of positions visited by the guard (`X`).
+    pub fn count_steps(&self) -> usize {
+        self.visited().len()
     }
 
     /// Tracks the guard's path and checks if it forms a cycle.
     ///
     /// Keeps a record of all locations visited by the guard. If a state repeats,
-    /// it indicates a cycle.
+    /// it indicates a cycle. The map itself is never modified.
     ///
     /// [`Guard`] is used to track locations. So that, if and only if the guard visits the same location with
     /// same direction, it's considered a cycle.
-    fn track_guard(&mut self) -> Option<HashSet<Guard>> {
+    fn track_guard(&self) -> Option<HashSet<Guard>> {
         let mut visited_locations = HashSet::new();
-        while let Some(guard) = self.guard {
+        let mut current = self.guard;
+
+        while let Some(guard) = current {
             if !visited_locations.insert(guard) {
                 return Some(visited_locations);
             }
 
-            let (delta_y, delta_x) = guard.dir.signum();
-            if let Some(next_loc) = guard.loc.delta(delta_x, delta_y) {
-                if next_loc.x < self.width && next_loc.y < self.height {
-                    if self.data[next_loc.y][next_loc.x] == '#' {
-                        self.guard = Some(Guard::new(guard.loc, guard.dir.rotate()));
-                    } else {
-                        // Keep the guard moving.
-                        self.guard = Some(Guard::new(next_loc, guard.dir));
-                        self.data[guard.loc.y][guard.loc.x] = '*';
-                    }
-                } else {
-                    // That's it, if the guard left the map, it means we couldn't trap her.
-                    return None;
-                }
-            } else {
-                // That's it, if the gaurd left the map, it means we couldn't trap her.
-                return None;
+            current = self.next_guard(guard);
+        }
+
+        // That's it, if the guard left the map, it means we couldn't trap her.
+        None
+    }
+
+    /// Same as [`Map::track_guard`], but reports the length of the loop the guard is stuck in.
+    ///
+    /// The length is the number of distinct guard states (location and direction) in the
+    /// cycle, not counting any states leading into it. Returns `None` if the guard leaves the map.
+    pub fn track_guard_cycle(&self) -> Option<usize> {
+        let mut first_seen = HashMap::new();
+        let mut current = self.guard;
+        let mut idx = 0;
+
+        while let Some(guard) = current {
+            if let Some(first) = first_seen.insert(guard, idx) {
+                return Some(idx - first);
             }
+
+            current = self.next_guard(guard);
+            idx += 1;
         }
 
-        Some(visited_locations)
+        None
     }
 
     /// Finds all possible trap positions where adding an obstacle would create a cycle.
     ///
-    /// Simulates adding an obstacle (`#`) at every open position (`.`) on the map and checks if it traps the guard.
+    /// An obstacle can only change the patrol if it sits on a cell the guard would otherwise
+    /// step on, so only the cells of the unobstructed path (but the guard's start) are tried.
+    /// This brings the cost down from `O(cells × path)` to `O(path × path)`.
     pub fn find_traps(&self) -> usize {
+        self.trap_positions().len()
+    }
+
+    /// Returns every `(x, y)` cell where adding an obstacle traps the guard in a cycle,
+    /// sorted by row then column. See [`Map::find_traps`].
+    pub fn trap_positions(&self) -> Vec<(usize, usize)> {
+        let mut traps: Vec<(usize, usize)> = self
+            .trap_candidates()
+            .into_iter()
+            .filter(|&cell| self.is_trap(cell))
+            .collect();
+
+        traps.sort_by_key(|&(x, y)| (y, x));
+        traps
+    }
+
+    /// Same as [`Map::find_traps`], but splits the candidate cells across threads.
+    pub fn find_traps_parallel(&self) -> usize {
+        let candidates = self.trap_candidates();
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = candidates.len().div_ceil(workers).max(1);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().filter(|&&cell| self.is_trap(cell)).count())
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        })
+    }
+
+    /// Returns the `(x, y)` cells worth trying an obstacle on: open cells on the guard's path.
+    ///
+    /// If the guard already loops without any extra obstacle, one placed off her path keeps her
+    /// looping, so every open cell is tried instead.
+    fn trap_candidates(&self) -> Vec<(usize, usize)> {
+        let cells: Vec<(usize, usize)> = if self.track_guard().is_some() {
+            (0..self.height)
+                .flat_map(|col| (0..self.width).map(move |row| (row, col)))
+                .collect()
+        } else {
+            self.visited().into_iter().collect()
+        };
+
+        cells
+            .into_iter()
+            // The guard's starting cell isn't `.`, so it's skipped too.
+            .filter(|&(row, col)| self.data[col][row] == '.')
+            .collect()
+    }
+
+    /// Checks whether an obstacle at `(x, y)` traps the guard in a cycle.
+    fn is_trap(&self, (row, col): (usize, usize)) -> bool {
+        // keep the original map, and simulate on this map with a new obstacle.
+        let mut simulated_map = self.clone();
+        simulated_map.data[col][row] = '#';
+
+        simulated_map.track_guard().is_some()
+    }
+
+    /// Brute-force version of [`Map::find_traps`] trying an obstacle on every open position.
+    fn find_traps_brute_force(&self) -> usize {
         let mut traps = 0;
         for col in 0..self.height {
             for row in 0..self.width {
-                if self.data[col][row] == '.' {
-                    // keep the original map, and simulate on this map with a new obstacle.
-                    let mut simulated_map = self.clone();
-                    simulated_map.data[col][row] = '#';
-
-                    if simulated_map.track_guard().is_some() {
-                        traps += 1;
-                    }
+                if self.data[col][row] == '.' && self.is_trap((row, col)) {
+                    traps += 1;
                 }
             }
         }
@@ -281,6 +421,12 @@ impl Map {
     }
 }
 
+impl From<&str> for Map {
+    fn from(value: &str) -> Self {
+        Self::new(util::parse_char_grid(value))
+    }
+}
+
 impl fmt::Display for Map {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for col in 0..self.height {
@@ -326,4 +472,149 @@ mod tests {
 
         assert_eq!(output, 6);
     }
+
+    #[test]
+    fn test_direction_char_round_trip() {
+        for dir in [
+            Direction::West,
+            Direction::East,
+            Direction::North,
+            Direction::South,
+        ] {
+            let c: char = dir.into();
+            assert_eq!(Direction::try_from(c), Ok(dir));
+        }
+    }
+
+    #[test]
+    fn test_map_visited() {
+        let map = Map::new(SAMPLE.lines().map(|l| l.chars().collect()).collect());
+        let start = map.guard.unwrap().loc;
+
+        let visited = map.visited();
+
+        assert_eq!(visited.len(), 41);
+        assert!(visited.contains(&(start.x, start.y)));
+    }
+
+    #[test]
+    fn test_find_traps_matches_brute_force() {
+        let map = Map::new(SAMPLE.lines().map(|l| l.chars().collect()).collect());
+
+        assert_eq!(map.find_traps(), map.find_traps_brute_force());
+    }
+
+    #[test]
+    fn test_find_traps_parallel() {
+        let map = Map::new(SAMPLE.lines().map(|l| l.chars().collect()).collect());
+
+        assert_eq!(map.find_traps_parallel(), map.find_traps());
+    }
+
+    #[test]
+    fn test_trap_positions() {
+        let map = Map::new(SAMPLE.lines().map(|l| l.chars().collect()).collect());
+        let start = map.guard.unwrap().loc;
+
+        let traps = map.trap_positions();
+
+        assert_eq!(traps.len(), 6);
+        assert!(!traps.contains(&(start.x, start.y)));
+    }
+
+    #[test]
+    fn test_walk_bounded() {
+        let map = Map::new(SAMPLE.lines().map(|l| l.chars().collect()).collect());
+
+        assert_eq!(map.clone().walk_bounded(1_000), StopReason::LeftMap);
+        assert_eq!(map.clone().walk_bounded(5), StopReason::StepCap);
+
+        let mut empty = Map::new(vec![vec!['.'; 3]; 3]);
+        assert_eq!(empty.walk_bounded(1_000), StopReason::NoGuard);
+    }
+
+    #[test]
+    fn test_map_steps() {
+        let map = Map::new(SAMPLE.lines().map(|l| l.chars().collect()).collect());
+
+        let steps: Vec<(Location, Direction)> = map.steps().take(5).collect();
+
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0], (Location::new(4, 6), Direction::North));
+        assert_eq!(steps[4], (Location::new(4, 2), Direction::North));
+    }
+
+    #[test]
+    fn test_map_from_str() {
+        let map = Map::from(SAMPLE);
+
+        assert_eq!(
+            map.guard,
+            Some(Guard::new(Location::new(4, 6), Direction::North))
+        );
+    }
+
+    #[test]
+    fn test_map_try_new() {
+        let grid = |s: &str| s.lines().map(|l| l.chars().collect()).collect();
+
+        assert!(Map::try_new(grid(SAMPLE)).is_ok());
+        assert!(matches!(
+            Map::try_new(grid("...\n.#.\n...")),
+            Err(MapError::NoGuard)
+        ));
+        assert!(matches!(
+            Map::try_new(grid("^..\n.#.\n..>")),
+            Err(MapError::MultipleGuards(2))
+        ));
+    }
+
+    #[test]
+    fn test_revisit_count() {
+        let map = Map::from(SAMPLE);
+
+        assert!(map.revisit_count() > 0);
+    }
+
+    #[test]
+    fn test_track_guard_leaves_map_untouched() {
+        let map = Map::from(SAMPLE);
+        let before = map.to_string();
+
+        // `map` isn't mutable, so `track_guard` has to work on a shared reference.
+        assert!(map.track_guard().is_none());
+        assert_eq!(map.to_string(), before);
+        assert_eq!(map.find_traps(), 6);
+    }
+
+    #[test]
+    fn test_track_guard_cycle() {
+        let map = Map::from(
+            r".#...
+....#
+.^...
+#....
+...#.",
+        );
+
+        assert_eq!(map.track_guard_cycle(), Some(12));
+        assert_eq!(Map::from(SAMPLE).track_guard_cycle(), None);
+    }
+
+    #[test]
+    fn test_find_traps_already_looping() {
+        let map = Map::from(
+            r".#...
+....#
+.^...
+#....
+...#.",
+        );
+
+        assert_eq!(map.visited().len(), 8);
+        assert_eq!(map.find_traps(), 13);
+        assert_eq!(map.find_traps(), map.find_traps_brute_force());
+        assert_eq!(map.find_traps_parallel(), 13);
+        assert_eq!(map.trap_positions().len(), 13);
+    }
 }
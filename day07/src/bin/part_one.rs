@@ -1,26 +1,15 @@
-use day07::Solver;
-use std::io::BufRead;
-use util::read_file;
+use day07::{solve_all, Equation, Solver};
+use util::{print_solution, read_input};
 
 fn main() {
-    let result: usize = read_file("input/07.txt")
+    let input = read_input("input/07.txt");
+    let equations: Vec<Equation> = input
         .lines()
-        .map_while(Result::ok)
-        .map(|line| {
-            let (target, sequence) = line.split_once(": ").unwrap();
-            (target.to_string(), sequence.to_string())
-        })
-        .map(|(target, seq)| {
-            let target = target.parse::<usize>().unwrap();
-            let seq: Vec<usize> = seq
-                .split(" ")
-                .map(|v| v.parse::<usize>().unwrap())
-                .collect();
-            (target, seq)
-        })
-        .filter(|(target, seq)| Solver::check(*target, seq))
-        .map(|(target, _seq)| target)
-        .sum();
+        .map(|line| Equation::parse(line).unwrap())
+        .collect();
 
-    println!("* Solution: {} *", result);
+    let solver = Solver::new();
+    let result = solve_all(&equations, &solver);
+
+    print_solution(1, result);
 }
@@ -1,23 +1,52 @@
 #![allow(dead_code)]
 
+use std::{collections::HashSet, fmt, thread};
+
+/// An operator that can be placed between two numbers of an equation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum Op {
+pub enum Op {
     Add,
     Mul,
     Concat,
+    /// Subtraction, only valid while the result stays non-negative.
+    Sub,
+    /// Division, only valid when `first` is evenly divisible by `second`.
+    Div,
 }
 
 impl Op {
-    fn evalute(&self, first: usize, second: usize) -> usize {
+    /// Applies the operator to `first` and `second`.
+    ///
+    /// Returns `None` if the result overflows a `u64` or the operation is invalid for the
+    /// given operands, see [`Op::Sub`] and [`Op::Div`].
+    pub fn evalute(&self, first: u64, second: u64) -> Option<u64> {
         match self {
-            Op::Add => first + second,
-            Op::Mul => first * second,
-            Op::Concat => format!("{first}{second}").parse().unwrap(),
+            Op::Add => first.checked_add(second),
+            Op::Mul => first.checked_mul(second),
+            Op::Concat => {
+                let digits = second.checked_ilog10().unwrap_or(0) + 1;
+                first
+                    .checked_mul(10_u64.checked_pow(digits)?)?
+                    .checked_add(second)
+            }
+            Op::Sub => first.checked_sub(second),
+            Op::Div => {
+                if second != 0 && first.is_multiple_of(second) {
+                    Some(first / second)
+                } else {
+                    None
+                }
+            }
         }
     }
+
+    /// Whether the result is never less than `first` for positive operands.
+    fn is_non_decreasing(&self) -> bool {
+        matches!(self, Op::Add | Op::Mul | Op::Concat)
+    }
 }
 
-struct Permutations {
+pub struct Permutations {
     op_count: usize,
     state: Vec<Op>,
     idx: usize,
@@ -25,12 +54,16 @@ struct Permutations {
 }
 
 impl Permutations {
-    fn new(op_count: usize, enable_concat: bool) -> Self {
+    pub fn new(op_count: usize, enable_concat: bool) -> Self {
         let operators = if enable_concat {
             vec![Op::Add, Op::Mul, Op::Concat]
         } else {
             vec![Op::Add, Op::Mul]
         };
+        Self::with_operators(op_count, operators)
+    }
+
+    fn with_operators(op_count: usize, operators: Vec<Op>) -> Self {
         Self {
             op_count,
             state: vec![Op::Add; op_count],
@@ -38,6 +71,27 @@ impl Permutations {
             operators,
         }
     }
+
+    /// Number of operator combinations for `op_count` slots, i.e. how many items
+    /// `Permutations::new(op_count, enable_concat)` yields, or `None` if it doesn't fit a `u64`.
+    pub fn total(op_count: usize, enable_concat: bool) -> Option<u64> {
+        let base: u64 = if enable_concat { 3 } else { 2 };
+        checked_total(base, op_count)
+    }
+
+    /// Number of combinations left, saturating at `usize::MAX`.
+    fn remaining(&self) -> usize {
+        checked_total(self.operators.len() as u64, self.op_count)
+            .and_then(|total| usize::try_from(total).ok())
+            .map_or(usize::MAX, |total| total.saturating_sub(self.idx))
+    }
+}
+
+/// `base^op_count`, or `None` on overflow.
+fn checked_total(base: u64, op_count: usize) -> Option<u64> {
+    u32::try_from(op_count)
+        .ok()
+        .and_then(|n| base.checked_pow(n))
 }
 
 impl Iterator for Permutations {
@@ -47,43 +101,195 @@ impl Iterator for Permutations {
         let base = self.operators.len();
 
         // If idx exceeds the total number of permutations, terminate
-        if self.idx >= base.pow(self.op_count as u32) {
+        if self.remaining() == 0 {
             return None;
         }
 
-        // Update the current state based on idx
+        // Update the current state based on idx, one base-`base` digit per position
+        let mut rest = self.idx;
         for i in 0..self.op_count {
-            // Calculate the current operator index for position i
-            let op_idx = (self.idx / base.pow(i as u32)) % base;
-            self.state[i] = self.operators[op_idx];
+            self.state[i] = self.operators[rest % base];
+            rest /= base;
         }
 
         self.idx += 1;
         Some(self.state.clone())
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining();
+        (remaining, Some(remaining))
+    }
 }
 
-#[derive(Default)]
+impl ExactSizeIterator for Permutations {}
+
 pub struct Solver {
-    with_concat: bool,
+    operators: Vec<Op>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Solver {
     pub fn new() -> Self {
-        Self { with_concat: false }
+        Self::with_ops(vec![Op::Add, Op::Mul])
     }
     pub fn with_concat() -> Self {
-        Self { with_concat: true }
+        Self::with_ops(vec![Op::Add, Op::Mul, Op::Concat])
+    }
+    /// Creates a solver trying the given operators, in order.
+    pub fn with_ops(ops: Vec<Op>) -> Self {
+        Self { operators: ops }
     }
-    pub fn check(&self, target: usize, sequence: &[usize]) -> bool {
-        let permutations = Permutations::new(sequence.len() - 1, self.with_concat);
 
-        for perm in permutations {
-            let mut result = sequence[0];
-            for (&input, op) in sequence.iter().skip(1).zip(perm) {
-                result = op.evalute(result, input);
+    /// Checks whether some assignment of operators, evaluated left-to-right, turns `sequence`
+    /// into `target`.
+    ///
+    /// Operators are tried recursively and a branch is abandoned as soon as its running
+    /// result exceeds `target`: none of `Add`, `Mul` and `Concat` can decrease it for positive
+    /// operands. No pruning happens once `Sub` or `Div` is part of the operator set.
+    /// Compared to trying every permutation, this skips most of the `base^(n-1)` assignments.
+    pub fn check(&self, target: u64, sequence: &[u64]) -> bool {
+        self.solve(target, sequence).is_some()
+    }
+
+    /// Finds the first assignment of operators, in the order [`Solver::check`] tries them, that
+    /// turns `sequence` into `target` when evaluated left-to-right.
+    ///
+    /// # Returns
+    /// - `Some(ops)`: The `sequence.len() - 1` operators to place between the numbers.
+    /// - `None`: If the equation can't be solved.
+    pub fn solve(&self, target: u64, sequence: &[u64]) -> Option<Vec<Op>> {
+        let (&first, rest) = sequence.split_first()?;
+
+        // Multiplying by zero is the only way to go back down with non-decreasing operators.
+        let prune = !rest.contains(&0) && self.operators.iter().all(|op| op.is_non_decreasing());
+        let mut ops = Vec::with_capacity(rest.len());
+
+        if self.solve_from(target, first, rest, prune, &mut ops) {
+            Some(ops)
+        } else {
+            None
+        }
+    }
+
+    fn solve_from(
+        &self,
+        target: u64,
+        acc: u64,
+        rest: &[u64],
+        prune: bool,
+        ops: &mut Vec<Op>,
+    ) -> bool {
+        if prune && acc > target {
+            return false;
+        }
+
+        let Some((&next, rest)) = rest.split_first() else {
+            return acc == target;
+        };
+
+        for &op in &self.operators {
+            // An overflowing branch can't reach the target.
+            let Some(acc) = op.evalute(acc, next) else {
+                continue;
+            };
+
+            ops.push(op);
+            if self.solve_from(target, acc, rest, prune, ops) {
+                return true;
+            }
+            ops.pop();
+        }
+
+        false
+    }
+
+    /// Same answer as [`Solver::check`], but walks the operands breadth-first, keeping the set of
+    /// values reachable after each operand instead of every branch.
+    ///
+    /// Branches that land on the same intermediate value are only expanded once, and the check
+    /// fails early as soon as no value at or below `target` is left.
+    pub fn check_memoized(&self, target: u64, sequence: &[u64]) -> bool {
+        self.reachable(target, sequence)
+            .last()
+            .is_some_and(|values| values.contains(&target))
+    }
+
+    /// Like [`Solver::check`], but the operators associate right-to-left: `a op b op c` is
+    /// evaluated as `a op (b op c)`, starting from the last operand.
+    ///
+    /// Grouping changes the result whenever operators are mixed, and even for a single
+    /// non-associative one such as `Concat`, `Sub` or `Div`.
+    pub fn check_rtl(&self, target: u64, sequence: &[u64]) -> bool {
+        let Some((&last, rest)) = sequence.split_last() else {
+            return false;
+        };
+
+        let values = rest
+            .iter()
+            .rev()
+            .fold(HashSet::from([last]), |values, &first| {
+                values
+                    .iter()
+                    .flat_map(|&acc| {
+                        self.operators
+                            .iter()
+                            .filter_map(move |op| op.evalute(first, acc))
+                    })
+                    .collect()
+            });
+
+        values.contains(&target)
+    }
+
+    /// Sets of values reachable after each operand of `sequence`, stopping at the first empty set.
+    ///
+    /// Empty if `sequence` is.
+    fn reachable(&self, target: u64, sequence: &[u64]) -> Vec<HashSet<u64>> {
+        let Some((&first, rest)) = sequence.split_first() else {
+            return Vec::new();
+        };
+
+        let prune = !rest.contains(&0) && self.operators.iter().all(|op| op.is_non_decreasing());
+        let mut layers = vec![HashSet::from([first])];
+
+        for &next in rest {
+            let values: HashSet<u64> = layers[layers.len() - 1]
+                .iter()
+                .flat_map(|&acc| {
+                    self.operators
+                        .iter()
+                        .filter_map(move |op| op.evalute(acc, next))
+                })
+                .filter(|&value| !prune || value <= target)
+                .collect();
+
+            let exhausted = values.is_empty();
+            layers.push(values);
+            if exhausted {
+                break;
             }
-            if target == result {
+        }
+
+        layers
+    }
+
+    /// Brute-force version of [`Solver::check`] evaluating every operator permutation.
+    fn check_brute_force(&self, target: u64, sequence: &[u64]) -> bool {
+        let permutations = Permutations::with_operators(sequence.len() - 1, self.operators.clone());
+
+        for perm in permutations {
+            let result = sequence
+                .iter()
+                .skip(1)
+                .zip(perm)
+                .try_fold(sequence[0], |acc, (&input, op)| op.evalute(acc, input));
+            if result == Some(target) {
                 return true;
             }
         }
@@ -92,6 +298,116 @@ impl Solver {
     }
 }
 
+/// Error returned when an equation line can't be parsed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line has no `: ` between the target and the operands.
+    MissingSeparator { line: String },
+    /// A target or operand that isn't a valid number.
+    InvalidNumber { line: String, value: String },
+    /// The line has a target but no operands.
+    MissingOperands { line: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingSeparator { line } => {
+                write!(f, "equation is missing the `: ` separator: {:?}", line)
+            }
+            ParseError::InvalidNumber { line, value } => {
+                write!(f, "invalid number {:?} in equation {:?}", value, line)
+            }
+            ParseError::MissingOperands { line } => {
+                write!(f, "equation has no operands: {:?}", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A calibration equation: a target and the operands to combine into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation {
+    pub target: u64,
+    pub operands: Vec<u64>,
+}
+
+impl Equation {
+    /// Parses an equation in the form `target: a b c ...`.
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let number = |value: &str| {
+            value.parse::<u64>().map_err(|_| ParseError::InvalidNumber {
+                line: line.to_string(),
+                value: value.to_string(),
+            })
+        };
+
+        let (target, operands) =
+            line.split_once(": ")
+                .ok_or_else(|| ParseError::MissingSeparator {
+                    line: line.to_string(),
+                })?;
+
+        let target = number(target)?;
+        let operands: Vec<u64> = operands
+            .split_whitespace()
+            .map(number)
+            .collect::<Result<_, _>>()?;
+        if operands.is_empty() {
+            return Err(ParseError::MissingOperands {
+                line: line.to_string(),
+            });
+        }
+
+        Ok(Self { target, operands })
+    }
+
+    /// Whether `solver` can make the operands evaluate to the target.
+    pub fn is_solvable(&self, solver: &Solver) -> bool {
+        solver.check(self.target, &self.operands)
+    }
+}
+
+/// Sums the targets of all equations in `input` that `solver` can make true.
+///
+/// Each line of `input` is an equation in the form `target: a b c ...`.
+///
+/// # Panics
+/// If a line isn't a valid equation, see [`Equation::parse`].
+pub fn calibration_result(input: &str, solver: &Solver) -> u64 {
+    input
+        .lines()
+        .map(|line| Equation::parse(line).unwrap())
+        .filter(|equation| equation.is_solvable(solver))
+        .map(|equation| equation.target)
+        .sum()
+}
+
+/// Same as [`calibration_result`] on already parsed equations, but solves them in parallel.
+pub fn solve_all(equations: &[Equation], solver: &Solver) -> u64 {
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = equations.len().div_ceil(workers).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = equations
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter(|equation| equation.is_solvable(solver))
+                        .map(|equation| equation.target)
+                        .sum::<u64>()
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -110,28 +426,24 @@ mod tests {
     #[test]
     fn test_op() {
         let op = Op::Add;
-        assert_eq!(op.evalute(81, 40), 121);
+        assert_eq!(op.evalute(81, 40), Some(121));
 
         let op = Op::Mul;
-        assert_eq!(op.evalute(5, 4), 20);
+        assert_eq!(op.evalute(5, 4), Some(20));
+
+        let op = Op::Concat;
+        assert_eq!(op.evalute(12, 345), Some(12345));
+        assert_eq!(op.evalute(12, 0), Some(120));
     }
 
     #[test]
     fn test_part_one() {
         let solver = Solver::new();
-        let input: usize = SAMPLE
+        let input: u64 = SAMPLE
             .lines()
-            .map(|line| line.split_once(": ").unwrap())
-            .map(|(target, seq)| {
-                let target = target.parse::<usize>().unwrap();
-                let seq: Vec<usize> = seq
-                    .split(" ")
-                    .map(|v| v.parse::<usize>().unwrap())
-                    .collect();
-                (target, seq)
-            })
-            .filter(|(target, seq)| solver.check(*target, seq))
-            .map(|(target, _seq)| target)
+            .map(|line| Equation::parse(line).unwrap())
+            .filter(|equation| equation.is_solvable(&solver))
+            .map(|equation| equation.target)
             .sum();
 
         assert_eq!(input, 3749);
@@ -140,21 +452,182 @@ mod tests {
     #[test]
     fn test_part_two() {
         let solver = Solver::with_concat();
-        let output: usize = SAMPLE
+        let output: u64 = SAMPLE
             .lines()
-            .map(|line| line.split_once(": ").unwrap())
-            .map(|(target, seq)| {
-                let target = target.parse::<usize>().unwrap();
-                let seq: Vec<usize> = seq
-                    .split(" ")
-                    .map(|v| v.parse::<usize>().unwrap())
-                    .collect();
-                (target, seq)
-            })
-            .filter(|(target, seq)| solver.check(*target, seq))
-            .map(|(target, _seq)| target)
+            .map(|line| Equation::parse(line).unwrap())
+            .filter(|equation| equation.is_solvable(&solver))
+            .map(|equation| equation.target)
             .sum();
 
         assert_eq!(output, 11387);
     }
+
+    #[test]
+    fn test_calibration_result() {
+        assert_eq!(calibration_result(SAMPLE, &Solver::new()), 3749);
+        assert_eq!(calibration_result(SAMPLE, &Solver::with_concat()), 11387);
+    }
+
+    #[test]
+    fn test_check_matches_brute_force() {
+        for solver in [Solver::new(), Solver::with_concat()] {
+            for line in SAMPLE.lines() {
+                let Equation { target, operands } = Equation::parse(line).unwrap();
+
+                assert_eq!(
+                    solver.check(target, &operands),
+                    solver.check_brute_force(target, &operands)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_with_zero_operand() {
+        // 100 * 0 + 7 only works if the branch above the target isn't pruned.
+        assert!(Solver::new().check(7, &[100, 0, 7]));
+    }
+
+    #[test]
+    fn test_solve_returns_operators() {
+        let (target, seq) = (3267, [81, 40, 27]);
+
+        let ops = Solver::new().solve(target, &seq).unwrap();
+
+        assert_eq!(ops.len(), 2);
+        let result = seq[1..]
+            .iter()
+            .zip(&ops)
+            .try_fold(seq[0], |acc, (&n, op)| op.evalute(acc, n));
+        assert_eq!(result, Some(target));
+
+        assert_eq!(Solver::new().solve(83, &[17, 5]), None);
+        assert_eq!(Solver::new().solve(5, &[]), None);
+        assert!(!Solver::new().check(5, &[]));
+    }
+
+    #[test]
+    fn test_concat_overflow_is_pruned() {
+        assert_eq!(Op::Concat.evalute(u64::MAX, 1), None);
+
+        // Both `*` and `||` overflow here, only `+` stays in range.
+        let seq = [1_844_674_407_370_955_161, 99];
+        assert!(!Solver::with_concat().check(u64::MAX, &seq));
+        assert!(Solver::with_concat().check(1_844_674_407_370_955_260, &seq));
+    }
+
+    #[test]
+    fn test_solver_with_sub_and_div() {
+        let solver = Solver::with_ops(vec![Op::Add, Op::Mul, Op::Sub, Op::Div]);
+
+        assert!(!Solver::new().check(10, &[15, 5]));
+        assert!(solver.check(10, &[15, 5]));
+        // Starts above the target, so it must not be pruned.
+        assert!(solver.check(10, &[20, 5, 5]));
+        assert_eq!(solver.solve(3, &[12, 4]), Some(vec![Op::Div]));
+
+        assert_eq!(Op::Div.evalute(12, 5), None);
+        assert_eq!(Op::Div.evalute(12, 0), None);
+        assert_eq!(Op::Sub.evalute(5, 12), None);
+    }
+
+    #[test]
+    fn test_equation_parse() {
+        let equation = Equation::parse("292: 11 6 16 20").unwrap();
+
+        assert_eq!(
+            equation,
+            Equation {
+                target: 292,
+                operands: vec![11, 6, 16, 20],
+            }
+        );
+        assert!(equation.is_solvable(&Solver::new()));
+    }
+
+    #[test]
+    fn test_equation_parse_errors() {
+        assert_eq!(
+            Equation::parse("292 11 6"),
+            Err(ParseError::MissingSeparator {
+                line: "292 11 6".to_string()
+            })
+        );
+        assert_eq!(
+            Equation::parse("292: 11 x"),
+            Err(ParseError::InvalidNumber {
+                line: "292: 11 x".to_string(),
+                value: "x".to_string()
+            })
+        );
+        assert_eq!(
+            Equation::parse("5: "),
+            Err(ParseError::MissingOperands {
+                line: "5: ".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_solve_all_matches_sequential() {
+        let equations: Vec<Equation> = SAMPLE
+            .lines()
+            .map(|line| Equation::parse(line).unwrap())
+            .collect();
+
+        for solver in [Solver::new(), Solver::with_concat()] {
+            assert_eq!(
+                solve_all(&equations, &solver),
+                calibration_result(SAMPLE, &solver)
+            );
+        }
+        assert_eq!(solve_all(&equations, &Solver::with_concat()), 11387);
+    }
+
+    #[test]
+    fn test_permutations_total() {
+        assert_eq!(Permutations::total(3, true), Some(27));
+        assert_eq!(Permutations::total(3, false), Some(8));
+        assert_eq!(Permutations::total(40, true), Some(3u64.pow(40)));
+        assert_eq!(Permutations::total(41, true), None);
+        assert_eq!(Permutations::total(64, false), None);
+        assert_eq!(Permutations::new(41, true).len(), usize::MAX);
+
+        let mut permutations = Permutations::new(3, true);
+        assert_eq!(permutations.len(), 27);
+        permutations.next();
+        assert_eq!(permutations.len(), 26);
+        assert_eq!(permutations.count(), 26);
+    }
+
+    #[test]
+    fn test_check_memoized_matches_check() {
+        for solver in [Solver::new(), Solver::with_concat()] {
+            for line in SAMPLE.lines() {
+                let Equation { target, operands } = Equation::parse(line).unwrap();
+
+                assert_eq!(
+                    solver.check_memoized(target, &operands),
+                    solver.check(target, &operands)
+                );
+            }
+
+            assert!(!solver.check_memoized(5, &[]));
+            assert_eq!(solver.check_memoized(5, &[]), solver.check(5, &[]));
+        }
+    }
+
+    #[test]
+    fn test_check_rtl() {
+        let solver = Solver::with_concat();
+
+        // 1 || (2 * 3) = 16, while no left-to-right assignment of 1 2 3 gives 16.
+        assert!(solver.check_rtl(16, &[1, 2, 3]));
+        assert!(!solver.check(16, &[1, 2, 3]));
+
+        // Mixing Add and Mul is direction sensitive too: 2 * (3 + 4) = 14.
+        let solver = Solver::new();
+        assert!(solver.check_rtl(14, &[2, 3, 4]));
+        assert!(!solver.check(14, &[2, 3, 4]));
+    }
 }
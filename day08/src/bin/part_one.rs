@@ -1,9 +1,9 @@
-use day08::City;
-use util::read_file_to_string;
+use day08::{City, Harmonics};
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file_to_string("input/08.txt").unwrap();
+    let input = read_input("input/08.txt");
     let city = City::from(&input);
-    let result = city.get_unique_antinode_count();
-    println!("* Solution: {result} *");
+    let result = city.get_unique_antinode_count(Harmonics::Single);
+    print_solution(1, result);
 }
@@ -0,0 +1,9 @@
+use day08::{City, Harmonics};
+use util::{print_solution, read_input};
+
+fn main() {
+    let input = read_input("input/08.txt");
+    let city = City::from(&input);
+    let result = city.get_unique_antinode_count(Harmonics::All);
+    print_solution(2, result);
+}
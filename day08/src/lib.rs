@@ -3,13 +3,17 @@
 use core::fmt;
 use std::{
     collections::{HashMap, HashSet},
+    io::{self, BufRead},
     ops,
 };
 
-#[derive(PartialEq, Eq, Clone, Copy, Hash)]
-struct Location {
-    x: usize,
-    y: usize,
+pub use util::Grid;
+
+/// A grid position; `x` is the row and `y` the column.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Location {
+    pub x: usize,
+    pub y: usize,
 }
 
 #[derive(PartialEq, Eq)]
@@ -18,15 +22,90 @@ struct Antenna {
     loc: Location,
 }
 
-pub struct Grid {
-    grid: Vec<Vec<char>>,
-    height: usize,
-    width: usize,
+/// Which antinodes a pair of same-frequency antennas produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Harmonics {
+    /// Only the two points where one antenna is twice as far as the other.
+    Single,
+    /// Every grid point in line with the pair, including the antennas themselves.
+    All,
+}
+
+impl Harmonics {
+    /// Which points along the line from an antenna, stepping away from its partner by the
+    /// antenna-to-antenna offset, are antinodes, as `(skip, take)`.
+    ///
+    /// `Single` skips the antenna itself and takes the next point only, so the antenna cells
+    /// only become antinodes if some other pair lands there. `All` starts at the antenna, which
+    /// deliberately marks both antennas, then keeps going until it leaves the grid.
+    fn span(self) -> (usize, usize) {
+        match self {
+            Harmonics::Single => (1, 1),
+            Harmonics::All => (0, usize::MAX),
+        }
+    }
+}
+
+/// Points from `start`, repeatedly moved by `step`, as long as they stay inside a grid of
+/// `bounds` = `(height, width)`. The first item is `start` itself, if it's in bounds.
+fn line_points(
+    start: Location,
+    step: (isize, isize),
+    bounds: (usize, usize),
+) -> impl Iterator<Item = Location> {
+    let (height, width) = bounds;
+    std::iter::successors(Some(start), move |loc| loc.delta(step.0, step.1))
+        .take_while(move |loc| loc.x < height && loc.y < width)
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    /// A cell that is neither `.` nor an alphanumeric antenna frequency.
+    InvalidFrequency { row: usize, col: usize, ch: char },
+    /// A row whose length differs from the first row's.
+    Ragged {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The map has no rows.
+    Empty,
+    /// Reading the input failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidFrequency { row, col, ch } => {
+                write!(
+                    f,
+                    "invalid antenna frequency {:?} at row {}, column {}",
+                    ch, row, col
+                )
+            }
+            ParseError::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {} has {} cells, expected {}", row, found, expected),
+            ParseError::Empty => write!(f, "city map is empty"),
+            ParseError::Io(err) => write!(f, "failed to read city map: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
 }
 
 #[derive(Debug)]
 pub struct City {
-    grid: Grid,
+    grid: Grid<char>,
     antennas: HashMap<char, HashSet<Location>>,
 }
 
@@ -85,51 +164,16 @@ impl fmt::Debug for Antenna {
     }
 }
 
-impl Grid {
-    fn new(data: Vec<Vec<char>>) -> Self {
-        let (height, width) = (data.len(), data[0].len());
-        Self {
-            grid: data,
-            height,
-            width,
-        }
-    }
-
-    /// Modifies the grid at the given location.
-    ///
-    /// Returns:
-    /// - `true`: if the location in bound of the grid.
-    /// - `false`: if the location out of the grid.
-    fn modify(&mut self, location: &Location, new_value: char) -> bool {
-        if location.x >= self.height || location.y >= self.width {
-            return false;
-        }
-
-        self.grid[location.x][location.y] = new_value;
-        true
-    }
-}
-
-impl fmt::Debug for Grid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f)?;
-        for row in 0..self.height {
-            writeln!(f, "{}", self.grid[row].iter().collect::<String>())?;
-        }
-        Ok(())
-    }
-}
-
 impl City {
     pub fn new(grid: Vec<Vec<char>>) -> Self {
         let (height, width) = (grid.len(), grid[0].len());
 
         let mut antennas = HashMap::new();
-        for row in 0..height {
-            for col in 0..width {
-                if grid[row][col] != '.' {
+        for (row, line) in grid.iter().enumerate().take(height) {
+            for (col, &cell) in line.iter().enumerate().take(width) {
+                if cell != '.' {
                     antennas
-                        .entry(grid[row][col])
+                        .entry(cell)
                         .or_insert_with(HashSet::new)
                         .insert(Location::new(row, col));
                 }
@@ -142,9 +186,48 @@ impl City {
         }
     }
 
+    /// Parses a city map, one row per line.
+    ///
+    /// # Panics
+    /// If the map is invalid, see [`City::from_reader`].
     pub fn from(data: &str) -> Self {
-        let grid: Vec<Vec<char>> = data.lines().map(|line| line.chars().collect()).collect();
-        Self::new(grid)
+        Self::from_reader(data.as_bytes()).unwrap()
+    }
+
+    /// Reads a city map, one row per line, where every cell is either `.` or an alphanumeric
+    /// antenna frequency, and every row has the same length.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, ParseError> {
+        let mut grid: Vec<Vec<char>> = Vec::new();
+        for (row, line) in reader.lines().enumerate() {
+            let line: Vec<char> = line?.trim_end().chars().collect();
+            if let Some(col) = line
+                .iter()
+                .position(|&ch| ch != '.' && !ch.is_alphanumeric())
+            {
+                return Err(ParseError::InvalidFrequency {
+                    row,
+                    col,
+                    ch: line[col],
+                });
+            }
+            if let Some(first) = grid.first() {
+                let expected = first.len();
+                if line.len() != expected {
+                    return Err(ParseError::Ragged {
+                        row,
+                        expected,
+                        found: line.len(),
+                    });
+                }
+            }
+            grid.push(line);
+        }
+
+        if grid.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        Ok(Self::new(grid))
     }
 
     fn cordination_add(cor: usize, delta: i8) -> Option<usize> {
@@ -155,59 +238,134 @@ impl City {
         }
     }
 
-    pub fn find_antinodes(&self) -> Grid {
-        let mut antinode_grid = Grid::new(vec![vec!['.'; self.grid.height]; self.grid.width]);
+    /// Locations of every antinode produced by pairs of same-frequency antennas.
+    pub fn antinode_locations(&self, harmonics: Harmonics) -> HashSet<Location> {
+        self.antinodes_by_frequency(harmonics)
+            .into_values()
+            .flatten()
+            .collect()
+    }
+
+    /// Antinode locations produced by each frequency on its own.
+    ///
+    /// Antinodes of different frequencies may coincide, so the sum of the set sizes can be larger
+    /// than the number of unique antinodes returned by [`City::antinode_locations`].
+    pub fn antinodes_by_frequency(&self, harmonics: Harmonics) -> HashMap<char, HashSet<Location>> {
+        self.antennas
+            .iter()
+            .map(|(&freq, locs)| (freq, self.frequency_antinodes(locs, harmonics)))
+            .collect()
+    }
+
+    fn frequency_antinodes(
+        &self,
+        locs: &HashSet<Location>,
+        harmonics: Harmonics,
+    ) -> HashSet<Location> {
+        let mut antinodes = HashSet::new();
+        let (skip, take) = harmonics.span();
+        let bounds = (self.grid.height(), self.grid.width());
+
+        let locs: Vec<Location> = locs.iter().cloned().collect();
+        for i in 0..locs.len() {
+            for j in i + 1..locs.len() {
+                let (ant, other) = (locs[i], locs[j]);
+                let (delta_x, delta_y) = ant - other;
+
+                antinodes.extend(
+                    line_points(ant, (delta_x, delta_y), bounds)
+                        .skip(skip)
+                        .take(take),
+                );
+                antinodes.extend(
+                    line_points(other, (-delta_x, -delta_y), bounds)
+                        .skip(skip)
+                        .take(take),
+                );
+            }
+        }
+
+        antinodes
+    }
+
+    /// A grid of the city's size with every antinode marked as `'#'`.
+    pub fn find_antinodes(&self, harmonics: Harmonics) -> Grid<char> {
+        let mut antinode_grid = Grid::new(vec![vec!['.'; self.grid.width()]; self.grid.height()]);
+        for antinode in self.antinode_locations(harmonics) {
+            if let Some(cell) = antinode_grid.get_mut(antinode.x, antinode.y) {
+                *cell = '#';
+            }
+        }
+
+        antinode_grid
+    }
+
+    /// Groups of three or more same-frequency antennas lying on a single line.
+    ///
+    /// Each group is sorted, and groups are returned in sorted order.
+    pub fn collinear_groups(&self) -> Vec<Vec<Location>> {
+        let mut groups = HashSet::new();
 
-        for (_freq, locs) in self.antennas.iter() {
+        for locs in self.antennas.values() {
             let locs: Vec<Location> = locs.iter().cloned().collect();
             for i in 0..locs.len() {
                 for j in i + 1..locs.len() {
-                    if i == j {
-                        continue;
-                    }
-                    let (ant, other) = (locs[i], locs[j]);
-                    let (delta_x, delta_y) = ant - other;
-
-                    let mut multiplier = 0;
-                    while let Some(new_antinode) =
-                        ant.delta(multiplier * delta_x, multiplier * delta_y)
-                    {
-                        if !antinode_grid.modify(&new_antinode, '#') {
-                            break;
-                        }
-
-                        multiplier += 1;
-                    }
-
-                    let mut multiplier = 0;
-                    while let Some(new_antinode) =
-                        other.delta(multiplier * -delta_x, multiplier * -delta_y)
-                    {
-                        if !antinode_grid.modify(&new_antinode, '#') {
-                            break;
-                        }
-
-                        multiplier += 1;
+                    let (dx, dy) = locs[j] - locs[i];
+                    let mut group: Vec<Location> = locs
+                        .iter()
+                        .filter(|&&loc| {
+                            let (ox, oy) = loc - locs[i];
+                            dx * oy - dy * ox == 0
+                        })
+                        .cloned()
+                        .collect();
+
+                    if group.len() >= 3 {
+                        group.sort();
+                        groups.insert(group);
                     }
                 }
             }
         }
 
-        antinode_grid
+        let mut groups: Vec<Vec<Location>> = groups.into_iter().collect();
+        groups.sort();
+        groups
     }
 
-    pub fn get_unique_antinode_count(&self) -> usize {
-        let antinode_grid = self.find_antinodes();
-        let mut antinode_count = 0;
-        for row in 0..antinode_grid.height {
-            for col in 0..antinode_grid.width {
-                if antinode_grid.grid[row][col] == '#' {
-                    antinode_count += 1;
-                }
+    /// Top-left and bottom-right corners of the smallest rectangle covering every antinode,
+    /// or `None` if there are none.
+    pub fn antinode_bounds(&self, harmonics: Harmonics) -> Option<(Location, Location)> {
+        let antinodes = self.antinode_locations(harmonics);
+        let min_x = antinodes.iter().map(|loc| loc.x).min()?;
+        let max_x = antinodes.iter().map(|loc| loc.x).max()?;
+        let min_y = antinodes.iter().map(|loc| loc.y).min()?;
+        let max_y = antinodes.iter().map(|loc| loc.y).max()?;
+
+        Some((Location::new(min_x, min_y), Location::new(max_x, max_y)))
+    }
+
+    /// Renders the city map with every antinode on an empty cell marked as `'#'`; antennas are
+    /// left as they are.
+    pub fn render(&self, harmonics: Harmonics) -> String {
+        let mut grid = self.grid.clone();
+        for antinode in self.antinode_locations(harmonics) {
+            if let Some(cell) = grid
+                .get_mut(antinode.x, antinode.y)
+                .filter(|cell| **cell == '.')
+            {
+                *cell = '#';
             }
         }
 
-        antinode_count
+        grid.rows()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn get_unique_antinode_count(&self, harmonics: Harmonics) -> usize {
+        self.antinode_locations(harmonics).len()
     }
 }
 
@@ -231,7 +389,188 @@ mod tests {
     fn test_city_from_str() {
         let city = City::from(SAMPLE);
 
-        let count = city.get_unique_antinode_count();
+        let count = city.get_unique_antinode_count(Harmonics::All);
         assert_eq!(count, 34);
     }
+
+    #[test]
+    fn test_single_harmonics() {
+        let city = City::from(SAMPLE);
+
+        assert_eq!(city.get_unique_antinode_count(Harmonics::Single), 14);
+        assert_eq!(city.get_unique_antinode_count(Harmonics::All), 34);
+    }
+
+    #[test]
+    fn test_non_square_grid() {
+        // 4 rows of 6 columns.
+        let city = City::from(
+            r"......
+...a..
+....a.
+......",
+        );
+
+        let antinodes = city.find_antinodes(Harmonics::All);
+        assert_eq!((antinodes.height(), antinodes.width()), (4, 6));
+        assert_eq!(city.get_unique_antinode_count(Harmonics::Single), 2);
+        assert_eq!(city.get_unique_antinode_count(Harmonics::All), 4);
+    }
+
+    #[test]
+    fn test_antinode_locations() {
+        let city = City::from(SAMPLE);
+
+        for (harmonics, expected) in [(Harmonics::Single, 14), (Harmonics::All, 34)] {
+            let antinodes = city.antinode_locations(harmonics);
+            assert_eq!(antinodes.len(), expected);
+            assert!(antinodes.iter().all(|loc| loc.x < 12 && loc.y < 12));
+        }
+    }
+
+    #[test]
+    fn test_antenna_cells_only_in_all_mode() {
+        let city = City::from(
+            r"......
+..a...
+...a..
+......",
+        );
+        let antennas = [Location::new(1, 2), Location::new(2, 3)];
+
+        let single = city.antinode_locations(Harmonics::Single);
+        assert!(antennas.iter().all(|loc| !single.contains(loc)));
+
+        let all = city.antinode_locations(Harmonics::All);
+        assert!(antennas.iter().all(|loc| all.contains(loc)));
+
+        let city = City::from(SAMPLE);
+        let all = city.antinode_locations(Harmonics::All);
+        assert!(city
+            .antennas
+            .values()
+            .flatten()
+            .all(|loc| all.contains(loc)));
+    }
+
+    #[test]
+    fn test_from_reader_rejects_invalid_frequency() {
+        let result = City::from_reader("....\n.a@.\n....".as_bytes());
+
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidFrequency {
+                row: 1,
+                col: 2,
+                ch: '@'
+            })
+        ));
+        assert!(matches!(
+            City::from_reader("".as_bytes()),
+            Err(ParseError::Empty)
+        ));
+        assert_eq!(
+            City::from_reader(SAMPLE.as_bytes())
+                .unwrap()
+                .get_unique_antinode_count(Harmonics::All),
+            34
+        );
+    }
+
+    #[test]
+    fn test_from_reader_rejects_ragged_rows() {
+        let result = City::from_reader("..a\n.a".as_bytes());
+
+        assert!(matches!(
+            result,
+            Err(ParseError::Ragged {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_render() {
+        let city = City::from(SAMPLE);
+
+        // One `Single` antinode sits on the topmost `A`; every antenna is an antinode in `All`.
+        let single = city.render(Harmonics::Single);
+        assert_eq!(single.matches('#').count(), 13);
+        assert_eq!(single.matches('A').count(), 3);
+
+        let all = city.render(Harmonics::All);
+        assert_eq!(all.matches('#').count(), 27);
+        assert_eq!(all.lines().count(), 12);
+    }
+
+    #[test]
+    fn test_antinodes_by_frequency() {
+        let city = City::from(SAMPLE);
+        let by_frequency = city.antinodes_by_frequency(Harmonics::All);
+        assert_eq!(by_frequency.len(), 2);
+
+        let union: HashSet<Location> = by_frequency.values().flatten().cloned().collect();
+        assert_eq!(union.len(), 34);
+
+        // Some `0` and `A` antinodes share a cell, so the per-frequency counts overlap.
+        let sum: usize = by_frequency.values().map(HashSet::len).sum();
+        assert!(sum > union.len());
+    }
+
+    #[test]
+    fn test_line_points_stops_at_edge() {
+        let points: Vec<Location> = line_points(Location::new(1, 1), (2, 3), (6, 8)).collect();
+        assert_eq!(
+            points,
+            vec![
+                Location::new(1, 1),
+                Location::new(3, 4),
+                Location::new(5, 7)
+            ]
+        );
+
+        let points: Vec<Location> = line_points(Location::new(1, 1), (-1, -1), (6, 8)).collect();
+        assert_eq!(points, vec![Location::new(1, 1), Location::new(0, 0)]);
+
+        assert_eq!(line_points(Location::new(6, 0), (1, 0), (6, 8)).count(), 0);
+    }
+
+    #[test]
+    fn test_collinear_groups() {
+        let city = City::from(
+            r"a.....
+..a...
+....a.
+.b..b.
+......",
+        );
+
+        assert_eq!(
+            city.collinear_groups(),
+            vec![vec![
+                Location::new(0, 0),
+                Location::new(1, 2),
+                Location::new(2, 4)
+            ]]
+        );
+        assert!(City::from(SAMPLE).collinear_groups().is_empty());
+    }
+
+    #[test]
+    fn test_antinode_bounds() {
+        let city = City::from(SAMPLE);
+
+        let (top_left, bottom_right) = city.antinode_bounds(Harmonics::All).unwrap();
+        assert!(top_left.x <= bottom_right.x && top_left.y <= bottom_right.y);
+        assert!(bottom_right.x < 12 && bottom_right.y < 12);
+        assert_eq!(
+            (top_left, bottom_right),
+            (Location::new(0, 0), Location::new(11, 11))
+        );
+
+        let lonely = City::from("....\n.a..\n....");
+        assert_eq!(lonely.antinode_bounds(Harmonics::All), None);
+    }
 }
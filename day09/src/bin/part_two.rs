@@ -1,11 +1,11 @@
 use day09::Files;
-use util::read_file_to_string;
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file_to_string("input/09.txt").unwrap();
+    let input = read_input("input/09.txt");
     let mut disk = Files::parse(&input);
     disk.defragment();
     let solution = disk.checksum();
 
-    println!("** Solution: {solution} **");
+    print_solution(2, solution);
 }
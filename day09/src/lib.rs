@@ -1,23 +1,76 @@
-use std::fmt;
+use std::{collections::HashSet, fmt};
 
 type Id = usize;
 const SPACE: Option<Id> = None;
 
+/// A disk compaction strategy: parse a disk map, compact it and checksum the result.
+pub trait Compactor {
+    fn parse(input: &str) -> Self
+    where
+        Self: Sized;
+    fn defragment(&mut self);
+    fn checksum(&self) -> u64;
+}
+
+/// Error returned when a disk map contains something other than digits.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Index of the offending character in the trimmed input.
+    pub index: usize,
+    pub ch: char,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid digit {:?} at index {}", self.ch, self.index)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Digits of a disk map with their index, ignoring surrounding whitespace.
+fn digits(input: &str) -> impl Iterator<Item = (usize, Result<usize, ParseError>)> + '_ {
+    input.trim().chars().enumerate().map(|(index, ch)| {
+        let digit = ch
+            .to_digit(10)
+            .map(|d| d as usize)
+            .ok_or(ParseError { index, ch });
+        (index, digit)
+    })
+}
+
+/// How fragmented a [`Disk`] is. See [`Disk::fragmentation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragStats {
+    /// Number of distinct files.
+    pub files: usize,
+    /// Number of contiguous runs of blocks belonging to the same file.
+    pub fragments: usize,
+    /// Number of contiguous runs of free blocks.
+    pub free_runs: usize,
+}
+
 pub struct Disk {
     map: Vec<Option<Id>>,
 }
 
 impl Disk {
+    /// Parses a dense disk map.
+    ///
+    /// # Panics
+    /// If the map contains anything but digits, see [`Disk::try_parse`].
     pub fn parse(input: &str) -> Self {
+        Self::try_parse(input).unwrap()
+    }
+
+    /// Parses a dense disk map: digits alternating between file and free-space lengths,
+    /// starting with a file. The last file doesn't need a trailing free-space length.
+    pub fn try_parse(input: &str) -> Result<Self, ParseError> {
         let mut blocks = Vec::new();
         let mut file_id: Id = 0;
 
-        for (idx, c) in input
-            .chars()
-            .enumerate()
-            .filter(|(_idx, c)| c.is_ascii_digit())
-        {
-            let length = c.to_digit(10).unwrap() as usize;
+        for (idx, length) in digits(input) {
+            let length = length?;
             let is_file = idx % 2 == 0;
 
             for _ in 0..length {
@@ -33,10 +86,68 @@ impl Disk {
             }
         }
 
-        Self { map: blocks }
+        Ok(Self { map: blocks })
     }
 
+    /// Streams a dense disk map as `(file id, length)` runs, with `None` for free space, without
+    /// expanding it block by block.
+    ///
+    /// # Panics
+    /// If the map contains anything but digits, see [`Disk::try_parse`].
+    pub fn runs(input: &str) -> impl Iterator<Item = (Option<Id>, usize)> + '_ {
+        digits(input).map(|(idx, length)| {
+            let id = if idx % 2 == 0 { Some(idx / 2) } else { SPACE };
+            (id, length.unwrap())
+        })
+    }
+
+    /// Same as [`Disk::checksum`], summing each run's positions as an arithmetic series.
+    pub fn runs_checksum<I>(runs: I) -> u64
+    where
+        I: IntoIterator<Item = (Option<Id>, usize)>,
+    {
+        let mut position = 0;
+        let mut sum = 0;
+        for (id, length) in runs {
+            let (start, length) = (position as u64, length as u64);
+            if let Some(id) = id {
+                // start + (start + 1) + ... + (start + length - 1)
+                let positions = length * start + length * length.saturating_sub(1) / 2;
+                sum += id as u64 * positions;
+            }
+            position += length as usize;
+        }
+
+        sum
+    }
+
+    /// Moves file blocks one at a time from the end of the disk into the leftmost free block,
+    /// until there are no gaps between file blocks.
+    ///
+    /// A left cursor looks for free blocks while a right cursor looks for file blocks to fill
+    /// them with, so every block is visited once.
     pub fn defragment(&mut self) {
+        if self.map.is_empty() {
+            return;
+        }
+
+        let (mut left, mut right) = (0, self.map.len() - 1);
+        while left < right {
+            if self.map[left].is_some() {
+                left += 1;
+            } else if self.map[right].is_none() {
+                right -= 1;
+            } else {
+                self.map.swap(left, right);
+                left += 1;
+                right -= 1;
+            }
+        }
+    }
+
+    /// Quadratic version of [`Disk::defragment`], searching for the last file block for every gap.
+    #[cfg(test)]
+    fn defragment_naive(&mut self) {
         let non_space_len = self.map.iter().filter(|&&b| b.is_some()).count();
         for idx in 0..non_space_len {
             // Skipe blocks.
@@ -48,15 +159,71 @@ impl Disk {
         }
     }
 
-    pub fn checksum(&self) -> usize {
+    /// Counts the files, file fragments and free-space runs on the disk.
+    pub fn fragmentation(&self) -> FragStats {
+        let mut ids = HashSet::new();
+        let (mut fragments, mut free_runs) = (0, 0);
+
+        for (idx, &block) in self.map.iter().enumerate() {
+            if idx > 0 && self.map[idx - 1] == block {
+                continue;
+            }
+            match block {
+                Some(id) => {
+                    ids.insert(id);
+                    fragments += 1;
+                }
+                None => free_runs += 1,
+            }
+        }
+
+        FragStats {
+            files: ids.len(),
+            fragments,
+            free_runs,
+        }
+    }
+
+    /// Sum of `position * id` over every file block, accumulated in `u64` so it doesn't
+    /// overflow on 32-bit targets.
+    pub fn checksum(&self) -> u64 {
         self.map
             .iter()
             .enumerate()
-            .filter_map(|(position, &block)| block.map(|v| v * position))
+            .filter_map(|(position, &block)| block.map(|v| v as u64 * position as u64))
             .sum()
     }
 }
 
+/// Moves single blocks, see [`Disk::defragment`].
+impl Compactor for Disk {
+    fn parse(input: &str) -> Self {
+        Disk::parse(input)
+    }
+
+    fn defragment(&mut self) {
+        Disk::defragment(self)
+    }
+
+    fn checksum(&self) -> u64 {
+        Disk::checksum(self)
+    }
+}
+
+/// Shows the disk one character per block: free blocks as `.` and file blocks as the last
+/// digit of their id, so ids from 10 on wrap around.
+impl fmt::Display for Disk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in &self.map {
+            match block {
+                Some(id) => write!(f, "{}", id % 10)?,
+                None => write!(f, ".")?,
+            }
+        }
+        Ok(())
+    }
+}
+
 struct File {
     id: Id,
     size: usize,
@@ -64,6 +231,14 @@ struct File {
     position: usize,
 }
 
+/// A file moved by [`Files::defragment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveRecord {
+    pub id: Id,
+    pub from: usize,
+    pub to: usize,
+}
+
 pub struct Files {
     list: Vec<File>,
 }
@@ -75,13 +250,22 @@ impl fmt::Debug for File {
 }
 
 impl Files {
+    /// Parses a dense disk map.
+    ///
+    /// # Panics
+    /// If the map contains anything but digits, see [`Files::try_parse`].
     pub fn parse(input: &str) -> Self {
+        Self::try_parse(input).unwrap()
+    }
+
+    /// Same as [`Disk::try_parse`], keeping one entry per file.
+    pub fn try_parse(input: &str) -> Result<Self, ParseError> {
         let mut files = Vec::new();
         let mut file_id = 0;
         let mut position = 0;
 
-        for (idx, ch) in input.trim().char_indices() {
-            let size = ch.to_digit(10).unwrap() as usize;
+        for (idx, size) in digits(input) {
+            let size = size?;
 
             if idx % 2 == 0 {
                 files.push(File {
@@ -94,10 +278,53 @@ impl Files {
             position += size;
         }
 
-        Self { list: files }
+        Ok(Self { list: files })
     }
 
-    pub fn defragment(&mut self) {
+    /// Moves whole files, highest id first, into the leftmost gap before them that fits.
+    ///
+    /// Files are parsed in id order, so they're already sorted by position and the list is only
+    /// sorted once at the end. The space a file leaves behind is never reused: every file still
+    /// to be moved has a lower id and lies to its left.
+    ///
+    /// Returns the files that moved, in the order they were moved.
+    pub fn defragment(&mut self) -> Vec<MoveRecord> {
+        let mut moves = Vec::new();
+        // (position, size) of the free space between consecutive files, left to right.
+        let mut gaps: Vec<(usize, usize)> = self
+            .list
+            .windows(2)
+            .map(|pair| {
+                let end = pair[0].position + pair[0].size;
+                (end, pair[1].position - end)
+            })
+            .collect();
+
+        for file in self.list.iter_mut().rev() {
+            let gap = gaps
+                .iter_mut()
+                .take_while(|(position, _)| *position < file.position)
+                .find(|(_, size)| *size >= file.size);
+
+            if let Some((position, size)) = gap {
+                moves.push(MoveRecord {
+                    id: file.id,
+                    from: file.position,
+                    to: *position,
+                });
+                file.position = *position;
+                *position += file.size;
+                *size -= file.size;
+            }
+        }
+
+        self.list.sort_by_key(|x| x.position);
+        moves
+    }
+
+    /// Version of [`Files::defragment`] re-sorting the files after every move.
+    #[cfg(test)]
+    fn defragment_naive(&mut self) {
         let max_id = self.list.last().unwrap().id;
         for id in (0..=max_id).rev() {
             let file_idx = self.list.iter().position(|x| x.id == id).unwrap();
@@ -128,11 +355,12 @@ impl Files {
         }
     }
 
-    pub fn checksum(&self) -> usize {
+    /// Same as [`Disk::checksum`], per block of every file.
+    pub fn checksum(&self) -> u64 {
         let mut sum = 0;
         for file in &self.list {
             for idx in file.position..(file.position + file.size) {
-                sum += idx * file.id;
+                sum += idx as u64 * file.id as u64;
             }
         }
 
@@ -140,10 +368,36 @@ impl Files {
     }
 }
 
+/// Moves whole files, see [`Files::defragment`].
+impl Compactor for Files {
+    fn parse(input: &str) -> Self {
+        Files::parse(input)
+    }
+
+    fn defragment(&mut self) {
+        Files::defragment(self);
+    }
+
+    fn checksum(&self) -> u64 {
+        Files::checksum(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// A disk map of `len` digits following a fixed pseudo-random pattern, without empty files.
+    fn synthetic_input(len: usize) -> String {
+        (0..len)
+            .map(|i| {
+                let digit = (i * 7 + i / 3) % 10;
+                let digit = if i % 2 == 0 { digit.max(1) } else { digit };
+                char::from(b'0' + digit as u8)
+            })
+            .collect()
+    }
+
     #[test]
     fn test_disk_checksum() {
         let input = "2333133121414131402";
@@ -164,4 +418,149 @@ mod tests {
 
         assert_eq!(output, 2858);
     }
+
+    #[test]
+    fn test_disk_defragment_matches_naive() {
+        let input = synthetic_input(2001);
+
+        let mut fast = Disk::parse(&input);
+        fast.defragment();
+        let mut naive = Disk::parse(&input);
+        naive.defragment_naive();
+
+        assert_eq!(fast.map, naive.map);
+        assert_eq!(fast.checksum(), naive.checksum());
+    }
+
+    #[test]
+    fn test_files_defragment_matches_naive() {
+        let input = synthetic_input(2001);
+
+        let mut fast = Files::parse(&input);
+        fast.defragment();
+        let mut naive = Files::parse(&input);
+        naive.defragment_naive();
+
+        assert_eq!(fast.checksum(), naive.checksum());
+    }
+
+    #[test]
+    fn test_disk_display() {
+        let mut disk = Disk::parse("2333133121414131402");
+        assert_eq!(
+            disk.to_string(),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+
+        disk.defragment();
+        assert_eq!(
+            disk.to_string(),
+            "0099811188827773336446555566.............."
+        );
+    }
+
+    #[test]
+    fn test_checksum_beyond_u32() {
+        // 30000 files of 9 blocks without gaps: the last products are around 8 * 10^9.
+        let files = 30_000;
+        let input = "90".repeat(files as usize);
+
+        // File `k` covers positions 9k..9k+9, adding k * (81k + 36).
+        let expected: u64 = (0..files).map(|k: u64| k * (81 * k + 36)).sum();
+        assert!(expected > u32::MAX as u64);
+
+        assert_eq!(Disk::parse(&input).checksum(), expected);
+        assert_eq!(Files::parse(&input).checksum(), expected);
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let error = ParseError { index: 3, ch: 'x' };
+        assert_eq!(Disk::try_parse("233x13").err(), Some(error));
+        let error = ParseError { index: 3, ch: 'x' };
+        assert_eq!(Files::try_parse("233x13").err(), Some(error));
+
+        // Odd length: the last file has no free space after it.
+        let disk = Disk::try_parse("12345\n").unwrap();
+        assert_eq!(disk.to_string(), "0..111....22222");
+        let files = Files::try_parse("12345").unwrap();
+        assert_eq!(files.list.len(), 3);
+        assert_eq!(files.checksum(), disk.checksum());
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let mut disk = Disk::parse("2333133121414131402");
+        let before = FragStats {
+            files: 10,
+            fragments: 10,
+            free_runs: 8,
+        };
+        assert_eq!(disk.fragmentation(), before);
+
+        disk.defragment();
+        let after = disk.fragmentation();
+        assert_eq!(after.files, 10);
+        assert!(after.free_runs <= 1);
+        assert!(disk.to_string().ends_with('.'));
+    }
+
+    #[test]
+    fn test_files_defragment_moves() {
+        let mut files = Files::parse("2333133121414131402");
+        let moves = files.defragment();
+
+        let moved: Vec<(Id, usize, usize)> = moves.iter().map(|m| (m.id, m.from, m.to)).collect();
+        assert_eq!(moved, vec![(9, 40, 2), (7, 32, 8), (4, 19, 12), (2, 11, 4)]);
+        assert!(moves.iter().all(|m| m.to < m.from));
+        assert_eq!(files.checksum(), 2858);
+    }
+
+    fn run<C: Compactor>(input: &str) -> u64 {
+        let mut compactor = C::parse(input);
+        compactor.defragment();
+        compactor.checksum()
+    }
+
+    #[test]
+    fn test_compactor() {
+        let input = "2333133121414131402";
+
+        assert_eq!(run::<Disk>(input), 1928);
+        assert_eq!(run::<Files>(input), 2858);
+    }
+
+    #[test]
+    fn test_files_defragment_fills_partially_used_gap() {
+        // 0...1.233: file 3 takes the front of the first gap, then file 2 has to see the single
+        // block that's left there rather than the gap's original size.
+        let mut files = Files::parse("1311102");
+        let moves = files.defragment();
+
+        let moved: Vec<(Id, usize, usize)> = moves.iter().map(|m| (m.id, m.from, m.to)).collect();
+        assert_eq!(moved, vec![(3, 7, 1), (2, 6, 3)]);
+        assert_eq!(files.checksum(), 19);
+
+        let mut naive = Files::parse("1311102");
+        naive.defragment_naive();
+        assert_eq!(naive.checksum(), 19);
+    }
+
+    #[test]
+    fn test_runs_checksum() {
+        let input = "2333133121414131402";
+
+        let runs: Vec<_> = Disk::runs(input).take(4).collect();
+        assert_eq!(runs, vec![(Some(0), 2), (None, 3), (Some(1), 3), (None, 3)]);
+        assert_eq!(
+            Disk::runs_checksum(Disk::runs(input)),
+            Disk::parse(input).checksum()
+        );
+
+        let input = synthetic_input(2001);
+        assert_eq!(
+            Disk::runs_checksum(Disk::runs(&input)),
+            Disk::parse(&input).checksum()
+        );
+    }
 }
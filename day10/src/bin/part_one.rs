@@ -1,10 +1,10 @@
 use day10::TopoMap;
-use util::read_file_to_string;
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file_to_string("input/10.txt").unwrap();
+    let input = read_input("input/10.txt");
     let map = TopoMap::from(input.as_str());
     let score = map.total_score();
 
-    println!("* Solution: {score} *");
+    print_solution(1, score);
 }
@@ -1,8 +1,7 @@
 #![allow(dead_code)]
 
-use core::fmt;
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     convert, ops,
 };
 
@@ -10,11 +9,7 @@ const EMPTY: u8 = u8::MAX;
 
 #[inline]
 fn abs_diff(lhs: u8, rhs: u8) -> u8 {
-    if lhs > rhs {
-        lhs - rhs
-    } else {
-        rhs - lhs
-    }
+    lhs.abs_diff(rhs)
 }
 
 #[derive(PartialEq, Eq, Hash)]
@@ -23,69 +18,67 @@ enum Direction {
     South,
     East,
     West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
 }
 
 impl Direction {
+    const CARDINALS: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+    ];
+    const DIAGONALS: [Direction; 4] = [
+        Direction::NorthEast,
+        Direction::NorthWest,
+        Direction::SouthEast,
+        Direction::SouthWest,
+    ];
+
     fn delta(&self) -> (i8, i8) {
         match self {
             Direction::North => (-1, 0),
             Direction::South => (1, 0),
-            Direction::East => (0, -1),
-            Direction::West => (0, 1),
+            Direction::East => (0, 1),
+            Direction::West => (0, -1),
+            Direction::NorthEast => (-1, 1),
+            Direction::NorthWest => (-1, -1),
+            Direction::SouthEast => (1, 1),
+            Direction::SouthWest => (1, -1),
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Point {
-    x: usize,
-    y: usize,
-}
+pub use util::Point;
 
-impl fmt::Debug for Point {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "(x: {}, y: {})", self.x, self.y)
-    }
-}
-
-impl Point {
-    #[inline]
-    fn cordination_add(cor: usize, delta: i8) -> Option<usize> {
-        if delta >= 0 {
-            cor.checked_add(delta as usize)
-        } else {
-            cor.checked_sub((-delta) as usize)
-        }
-    }
-
-    fn delta(&self, delta_x: i8, delta_y: i8) -> Option<Self> {
-        if let Some(y) = Self::cordination_add(self.y, delta_y) {
-            if let Some(x) = Self::cordination_add(self.x, delta_x) {
-                return Some(Point { x, y });
-            }
-        }
-        None
-    }
+/// Summary statistics over every trailhead of a [`TopoMap`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailSummary {
+    pub trailheads: usize,
+    pub total_score: usize,
+    pub total_rating: usize,
+    /// Mean score per trailhead, `0.0` if there are none.
+    pub avg_score: f64,
 }
 
 pub struct TopoMap {
     contours: Vec<Vec<u8>>,
     width: usize,
     height: usize,
+    /// Height gained by every step of a trail.
+    step: u8,
+    /// Whether trails may also move diagonally.
+    diagonals: bool,
+    /// Whether trails run downhill, from `9` to `0`.
+    descending: bool,
 }
 
 impl convert::From<&str> for TopoMap {
     fn from(value: &str) -> Self {
-        let data = value
-            .lines()
-            .map(|line| {
-                line.trim()
-                    .chars()
-                    .map(|c| c.to_digit(10).unwrap_or(EMPTY as u32) as u8)
-                    .collect::<Vec<u8>>()
-            })
-            .collect();
-        Self::new(data)
+        Self::new(util::parse_digit_grid_or(value, EMPTY))
     }
 }
 
@@ -93,7 +86,7 @@ impl ops::Index<&Point> for TopoMap {
     type Output = u8;
 
     fn index(&self, point: &Point) -> &Self::Output {
-        if !self.in_bound(point) {
+        if !point.in_bounds(self.height, self.width) {
             panic!(
                 "Point {:?} out of bound, Map's dimentions: (height: {}, width: {})",
                 point, self.height, self.width
@@ -105,43 +98,80 @@ impl ops::Index<&Point> for TopoMap {
 }
 
 impl TopoMap {
-    pub fn new(data: Vec<Vec<u8>>) -> Self {
-        let (height, width) = (data.len(), data[0].len());
+    /// Creates a map from rows of heights. Rows shorter than the longest one are padded with
+    /// impassable cells.
+    pub fn new(mut data: Vec<Vec<u8>>) -> Self {
+        let height = data.len();
+        let width = data.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut data {
+            row.resize(width, EMPTY);
+        }
         Self {
             contours: data,
             width,
             height,
+            step: 1,
+            diagonals: false,
+            descending: false,
         }
     }
 
-    #[inline]
-    fn in_bound(&self, point: &Point) -> bool {
-        point.x < self.height && point.y < self.width
+    /// Sets the height a trail has to gain with every step, 1 by default.
+    pub fn with_step(mut self, step: u8) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Enables or disables moving diagonally, in addition to the four cardinal directions.
+    pub fn with_diagonals(mut self, diagonals: bool) -> Self {
+        self.diagonals = diagonals;
+        self
+    }
+
+    /// Makes trails run downhill: trailheads are the `9`s, trails lose height with every
+    /// step and end at a `0`.
+    pub fn with_descending(mut self, descending: bool) -> Self {
+        self.descending = descending;
+        self
+    }
+
+    /// Heights at which a trail starts and ends.
+    fn trail_ends(&self) -> (u8, u8) {
+        if self.descending {
+            (9, 0)
+        } else {
+            (0, 9)
+        }
     }
 
     /// Returns valid neighbors to some point.
     ///
-    /// Neighbors only differ from the `point` in height by one.
+    /// Neighbors are exactly [`TopoMap::with_step`] higher than the `point`, or lower when
+    /// [descending](TopoMap::with_descending).
     fn valid_neighbors(&self, point: &Point) -> Option<Vec<Point>> {
-        let directions = vec![
-            Direction::North,
-            Direction::South,
-            Direction::East,
-            Direction::West,
-        ];
+        let diagonals: &[Direction] = if self.diagonals {
+            &Direction::DIAGONALS
+        } else {
+            &[]
+        };
+        let directions = Direction::CARDINALS.iter().chain(diagonals);
 
         let mut neighbors = Vec::new();
 
-        for dir in &directions {
+        for dir in directions {
             let (delta_x, delta_y) = dir.delta();
 
             if let Some(n) = point.delta(delta_x, delta_y) {
-                if !self.in_bound(&n) || self[&n] == EMPTY {
+                if !n.in_bounds(self.height, self.width) || self[&n] == EMPTY {
                     continue;
                 }
 
-                let diff = self[&n].saturating_sub(self[point]);
-                if diff == 1 {
+                let diff = if self.descending {
+                    self[point].saturating_sub(self[&n])
+                } else {
+                    self[&n].saturating_sub(self[point])
+                };
+                if diff == self.step {
                     neighbors.push(n);
                 }
             }
@@ -154,24 +184,76 @@ impl TopoMap {
         }
     }
 
-    pub fn total_score(&self) -> usize {
-        let mut total_score = 0;
-        for x in 0..self.height {
-            for y in 0..self.width {
-                if self.contours[x][y] == 0 {
-                    let trailhead = Point { x, y };
-                    total_score += self.unique_paths(trailhead);
-                }
-            }
+    /// Every `0` on the map, or `9` when descending, row by row.
+    fn trailheads(&self) -> Vec<Point> {
+        let (start, _) = self.trail_ends();
+        (0..self.height)
+            .flat_map(|x| (0..self.width).map(move |y| Point { x, y }))
+            .filter(|point| self[point] == start)
+            .collect()
+    }
+
+    /// Score of every trailhead, see [`TopoMap::unique_paths`].
+    pub fn trailhead_scores(&self) -> Vec<(Point, usize)> {
+        self.trailheads()
+            .into_iter()
+            .map(|head| (head, self.unique_paths(head)))
+            .collect()
+    }
+
+    /// Rating of every trailhead: the number of distinct trails starting there.
+    pub fn trailhead_ratings(&self) -> Vec<(Point, usize)> {
+        let counts = self.path_counts();
+        self.trailheads()
+            .into_iter()
+            .map(|head| (head, counts[&head]))
+            .collect()
+    }
+
+    /// Counts trailheads and totals their scores and ratings, walking each trailhead once.
+    pub fn summary(&self) -> TrailSummary {
+        let heads = self.trailheads();
+        let counts = self.path_counts();
+
+        let total_score: usize = heads.iter().map(|&head| self.unique_paths(head)).sum();
+        let total_rating = heads.iter().map(|head| counts[head]).sum();
+        let avg_score = if heads.is_empty() {
+            0.0
+        } else {
+            total_score as f64 / heads.len() as f64
+        };
+
+        TrailSummary {
+            trailheads: heads.len(),
+            total_score,
+            total_rating,
+            avg_score,
         }
+    }
 
-        total_score
+    pub fn total_score(&self) -> usize {
+        self.trailhead_scores()
+            .iter()
+            .map(|&(_, score)| score)
+            .sum()
     }
 
+    /// Score of a trailhead: the number of distinct `9`s reachable from `head`.
     pub fn unique_paths(&self, head: Point) -> usize {
+        self.reachable_peaks(head).len()
+    }
+
+    /// Every `9` reachable from `head`.
+    ///
+    /// Every cell is expanded at most once, whichever route reaches it first. Routes only
+    /// ever differ in how they get to a cell, not in what can be reached from it, so skipping
+    /// a visited cell never loses a peak, and a peak reached twice is only recorded once.
+    pub fn reachable_peaks(&self, head: Point) -> HashSet<Point> {
         let mut stack = VecDeque::from([head]);
         let mut visited = HashSet::new();
-        let mut score = 0;
+        let mut peaks = HashSet::new();
+
+        let (_, end) = self.trail_ends();
 
         while let Some(current) = stack.pop_back() {
             // Skip already visited nodes
@@ -179,35 +261,60 @@ impl TopoMap {
                 continue;
             }
 
-            // Check if current point is height 9
-            if self[&current] == 9 {
-                score += 1;
+            // Check if current point ends a trail
+            if self[&current] == end {
+                peaks.insert(current);
             }
 
             // Get valid neighbors and add them to the stack
             if let Some(neighbors) = self.valid_neighbors(&current) {
-                stack.extend(neighbors.into_iter());
+                stack.extend(neighbors);
             }
 
             // Mark as visited
             visited.insert(current);
         }
 
-        score
+        peaks
     }
 
+    /// Sum of the ratings of every trailhead, i.e. the number of distinct trails from any `0` to
+    /// any `9`.
     pub fn total_rating(&self) -> usize {
-        let mut total_score = 0;
-        for x in 0..self.height {
-            for y in 0..self.width {
-                if self.contours[x][y] == 0 {
-                    let trailhead = Point { x, y };
-                    total_score += self.count_paths(trailhead);
-                }
-            }
+        self.trailhead_ratings()
+            .iter()
+            .map(|&(_, rating)| rating)
+            .sum()
+    }
+
+    /// Number of distinct trails from every cell to a `9` (or `0` when descending), computed
+    /// once for the whole map.
+    ///
+    /// Cells are visited from the trails' end backwards, so every neighbor a trail can step to
+    /// already has its count when a cell is reached.
+    fn path_counts(&self) -> HashMap<Point, usize> {
+        let mut cells: Vec<Point> = (0..self.height)
+            .flat_map(|x| (0..self.width).map(move |y| Point { x, y }))
+            .filter(|point| self[point] != EMPTY)
+            .collect();
+        let (_, end) = self.trail_ends();
+        cells.sort_by_key(|point| self[point].abs_diff(end));
+
+        let mut counts = HashMap::new();
+        for point in cells {
+            let count = if self[&point] == end {
+                1
+            } else {
+                self.valid_neighbors(&point)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|n| counts[n])
+                    .sum()
+            };
+            counts.insert(point, count);
         }
 
-        total_score
+        counts
     }
 
     fn count_paths(&self, current: Point) -> usize {
@@ -222,7 +329,7 @@ impl TopoMap {
 
             // Get valid neighbors and add them to the stack
             if let Some(neighbors) = self.valid_neighbors(&current) {
-                stack.extend(neighbors.into_iter());
+                stack.extend(neighbors);
             }
         }
 
@@ -256,4 +363,166 @@ mod tests {
         let score = map.total_rating();
         assert_eq!(score, 81);
     }
+
+    #[test]
+    fn test_memoized_rating_matches_count_paths() {
+        let map = TopoMap::from(SAMPLE);
+
+        let mut expected = 0;
+        for x in 0..map.height {
+            for y in 0..map.width {
+                if map.contours[x][y] == 0 {
+                    expected += map.count_paths(Point { x, y });
+                }
+            }
+        }
+
+        assert_eq!(map.total_rating(), expected);
+    }
+
+    #[test]
+    fn test_unique_paths_shared_midpoint() {
+        // Both trailheads go through the `4`, which leads to two peaks.
+        let map = TopoMap::from(
+            r"0123...9.
+...456789
+0123.....",
+        );
+
+        assert_eq!(map.unique_paths(Point { x: 0, y: 0 }), 2);
+        assert_eq!(map.unique_paths(Point { x: 2, y: 0 }), 2);
+        assert_eq!(map.total_score(), 4);
+    }
+
+    #[test]
+    fn test_trailhead_scores_and_ratings() {
+        let map = TopoMap::from(SAMPLE);
+
+        let scores = map.trailhead_scores();
+        let ratings = map.trailhead_ratings();
+        assert_eq!(scores.len(), 9);
+        assert_eq!(scores.iter().map(|&(_, s)| s).sum::<usize>(), 36);
+        assert_eq!(ratings.iter().map(|&(_, r)| r).sum::<usize>(), 81);
+        assert!(scores.iter().chain(&ratings).all(|(p, _)| map[p] == 0));
+    }
+
+    #[test]
+    fn test_with_step() {
+        let map = TopoMap::from("0369\n1245");
+        assert_eq!(map.total_score(), 0);
+
+        let map = map.with_step(3);
+        assert_eq!(map.total_score(), 1);
+        assert_eq!(map.total_rating(), 1);
+
+        let map = TopoMap::from(SAMPLE).with_step(1);
+        assert_eq!((map.total_score(), map.total_rating()), (36, 81));
+    }
+
+    #[test]
+    fn test_with_diagonals() {
+        // A single trail running down the main diagonal.
+        let input: Vec<String> = (0..10)
+            .map(|row| {
+                (0..10)
+                    .map(|col| {
+                        if row == col {
+                            char::from(b'0' + row)
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let map = TopoMap::from(input.join("\n").as_str());
+        assert_eq!(map.total_score(), 0);
+
+        let map = map.with_diagonals(true);
+        assert_eq!(map.total_score(), 1);
+
+        let map = TopoMap::from(SAMPLE).with_diagonals(false);
+        assert_eq!((map.total_score(), map.total_rating()), (36, 81));
+    }
+
+    #[test]
+    fn test_ragged_map() {
+        let map = TopoMap::from(
+            r"0123
+7654
+89.
+.",
+        );
+
+        assert_eq!((map.height, map.width), (4, 4));
+        assert_eq!(map[&Point { x: 2, y: 3 }], EMPTY);
+        assert_eq!(map[&Point { x: 3, y: 3 }], EMPTY);
+        assert_eq!(map.total_score(), 1);
+        assert_eq!(map.total_rating(), 1);
+    }
+
+    #[test]
+    fn test_reachable_peaks() {
+        let map = TopoMap::from(SAMPLE);
+
+        let counts: Vec<usize> = map
+            .trailheads()
+            .into_iter()
+            .map(|head| {
+                let peaks = map.reachable_peaks(head);
+                assert!(peaks.iter().all(|p| map[p] == 9));
+                peaks.len()
+            })
+            .collect();
+        assert_eq!(counts, vec![5, 6, 5, 3, 1, 3, 5, 3, 5]);
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = TopoMap::from(SAMPLE).summary();
+
+        let zeros = SAMPLE.chars().filter(|&c| c == '0').count();
+        assert_eq!(summary.trailheads, zeros);
+        assert_eq!(summary.total_score, 36);
+        assert_eq!(summary.total_rating, 81);
+        assert_eq!(summary.avg_score, 4.0);
+    }
+
+    #[test]
+    fn test_descending() {
+        let map = TopoMap::from(SAMPLE).with_descending(true);
+
+        assert!(map.trailheads().iter().all(|p| map[p] == 9));
+        // Every 0-to-9 trail is a 9-to-0 trail walked backwards, so both the number of trails
+        // and the number of connected (0, 9) pairs are the same in either direction.
+        let ascending = TopoMap::from(SAMPLE);
+        assert_eq!(map.total_rating(), ascending.total_rating());
+        assert_eq!(map.total_score(), ascending.total_score());
+        assert_eq!((map.total_score(), map.total_rating()), (36, 81));
+    }
+
+    #[test]
+    fn test_diagonal_deltas() {
+        let combined = |a: Direction, b: Direction| {
+            let ((ax, ay), (bx, by)) = (a.delta(), b.delta());
+            (ax + bx, ay + by)
+        };
+
+        assert_eq!(
+            Direction::NorthEast.delta(),
+            combined(Direction::North, Direction::East)
+        );
+        assert_eq!(
+            Direction::NorthWest.delta(),
+            combined(Direction::North, Direction::West)
+        );
+        assert_eq!(
+            Direction::SouthEast.delta(),
+            combined(Direction::South, Direction::East)
+        );
+        assert_eq!(
+            Direction::SouthWest.delta(),
+            combined(Direction::South, Direction::West)
+        );
+    }
 }
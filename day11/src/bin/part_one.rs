@@ -1,10 +1,10 @@
 use day11::Stones;
-use util::read_file_to_string;
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file_to_string("input/11.txt").unwrap();
+    let input = read_input("input/11.txt");
     let mut stones = Stones::new(input.as_str());
     let solution = stones.repeat(75);
 
-    println!("* Solution: {solution} *");
+    print_solution(1, solution);
 }
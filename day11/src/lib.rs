@@ -1,11 +1,72 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
+
+/// Error returned when the input contains something other than stone numbers.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub token: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid stone {:?}", self.token)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error returned when blinking no longer fits the integer types.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OverflowError {
+    /// A stone grows past `usize::MAX` when multiplied by `factor`.
+    Stone { stone: usize, factor: usize },
+    /// The number of stones grows past `u64::MAX`.
+    Count,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowError::Stone { stone, factor } => {
+                write!(f, "stone {} overflows when multiplied by {}", stone, factor)
+            }
+            OverflowError::Count => write!(f, "number of stones overflows a u64"),
+        }
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+/// Parses whitespace separated stone numbers.
+fn parse_stones(input: &str) -> Result<Vec<usize>, ParseError> {
+    input
+        .split_whitespace()
+        .map(|s| {
+            s.parse::<usize>().map_err(|_| ParseError {
+                token: s.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Multiplier applied to stones that neither are `0` nor split.
+const FACTOR: usize = 2024;
 
 pub struct StoneBlinker {
     transfomer: HashMap<usize, Vec<usize>>,
+    factor: usize,
+    /// Number of stones a `(stone, blinks)` pair turns into.
+    counts: HashMap<(usize, usize), u64>,
+}
+
+impl Default for StoneBlinker {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StoneBlinker {
-    fn new() -> Self {
+    /// Creates a blinker with the transitions of a few frequent stones already cached.
+    pub fn new() -> Self {
         // seed the table with some frequent values.
         let dp = HashMap::from([
             (0, vec![1]),
@@ -20,11 +81,28 @@ impl StoneBlinker {
             (8096, vec![80, 96]),
         ]);
 
-        Self { transfomer: dp }
+        Self {
+            transfomer: dp,
+            factor: FACTOR,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Creates a blinker multiplying stones that neither are `0` nor split by `factor`
+    /// instead of 2024.
+    pub fn with_factor(factor: usize) -> Self {
+        Self {
+            transfomer: HashMap::new(),
+            factor,
+            counts: HashMap::new(),
+        }
     }
 
     #[inline]
     /// Splits `usize` number with into two `usize` halves.
+    ///
+    /// Each half is parsed as a number, which drops its leading zeros: `2007` splits into
+    /// `20` and `7`, and `1000` into `10` and `0`.
     fn split(value: String) -> (usize, usize) {
         let mid_idx = value.len() / 2;
         let (first, second) = (&value[..mid_idx], &value[mid_idx..]);
@@ -34,78 +112,236 @@ impl StoneBlinker {
     }
 
     /// Blinks at single stone.
-    fn blink_at(stone: usize) -> Vec<usize> {
-        // No need to calculate if it's equals to `0`, since it's already seeded
-        // into the transformer `HashMap`.
-        if stone.to_string().len() % 2 == 0 {
+    ///
+    /// - `0` becomes `1`.
+    /// - A stone with an even number of digits splits into its left and right halves.
+    /// - Any other stone is multiplied by 2024, or fails with an [`OverflowError`] if the
+    ///   product doesn't fit in a `usize`.
+    pub fn blink_at(stone: usize) -> Result<Vec<usize>, OverflowError> {
+        Self::blink_with(stone, FACTOR)
+    }
+
+    /// Same as [`StoneBlinker::blink_at`], multiplying by `factor`.
+    fn blink_with(stone: usize, factor: usize) -> Result<Vec<usize>, OverflowError> {
+        if stone == 0 {
+            Ok(vec![1])
+        } else if stone.to_string().len().is_multiple_of(2) {
             let string = stone.to_string();
             let (first, second) = Self::split(string);
-            vec![first, second]
+            Ok(vec![first, second])
         } else {
-            vec![stone * 2024]
+            let product = stone
+                .checked_mul(factor)
+                .ok_or(OverflowError::Stone { stone, factor })?;
+            Ok(vec![product])
         }
     }
 
-    fn get(&mut self, stone: usize) -> Vec<usize> {
+    /// Same as [`StoneBlinker::blink_at`] with this blinker's factor, caching the result for the
+    /// next time `stone` shows up.
+    pub fn get(&mut self, stone: usize) -> Result<Vec<usize>, OverflowError> {
         if let Some(stones) = self.transfomer.get(&stone) {
-            return stones.clone();
+            return Ok(stones.clone());
         }
 
-        let calc = Self::blink_at(stone);
+        let calc = Self::blink_with(stone, self.factor)?;
         self.transfomer.insert(stone, calc.clone());
 
-        calc
+        Ok(calc)
+    }
+
+    /// Counts the stones a single `stone` turns into after `blinks` blinks.
+    ///
+    /// Results are memoized per `(stone, blinks)`, so the many stones sharing a value at the
+    /// same depth are only expanded once.
+    pub fn count_after(&mut self, stone: usize, blinks: usize) -> Result<u64, OverflowError> {
+        if blinks == 0 {
+            return Ok(1);
+        }
+        if let Some(&count) = self.counts.get(&(stone, blinks)) {
+            return Ok(count);
+        }
+
+        let mut count: u64 = 0;
+        for s in self.get(stone)? {
+            count = count
+                .checked_add(self.count_after(s, blinks - 1)?)
+                .ok_or(OverflowError::Count)?;
+        }
+        self.counts.insert((stone, blinks), count);
+
+        Ok(count)
+    }
+}
+
+/// Stones kept in their left-to-right order.
+///
+/// Grows exponentially, so it's only practical for a few blinks. Use [`Stones`] to count them.
+pub struct StoneLine {
+    stones: Vec<usize>,
+}
+
+impl From<&str> for StoneLine {
+    fn from(input: &str) -> Self {
+        Self {
+            stones: parse_stones(input).unwrap(),
+        }
+    }
+}
+
+impl StoneLine {
+    /// Blinks once, replacing every stone with what it turns into in place.
+    ///
+    /// # Panics
+    /// If a stone overflows, see [`StoneBlinker::blink_at`].
+    pub fn blink(&mut self) -> &mut Self {
+        self.stones = self
+            .stones
+            .iter()
+            .flat_map(|&stone| StoneBlinker::blink_at(stone).unwrap_or_else(|e| panic!("{e}")))
+            .collect();
+        self
+    }
+
+    pub fn stones(&self) -> &[usize] {
+        &self.stones
     }
 }
 
 pub struct Stones {
-    freqs: HashMap<usize, usize>,
+    /// How many stones carry each number. Counts are `u64` as they pass `u32::MAX` well
+    /// before 75 blinks.
+    freqs: HashMap<usize, u64>,
     blinker: StoneBlinker,
 }
 
 impl Stones {
+    /// Parses whitespace separated stone numbers.
+    ///
+    /// # Panics
+    /// If a token isn't a number, see [`Stones::try_new`].
     pub fn new(input: &str) -> Self {
-        let stones: Vec<_> = input
-            .trim()
-            .split(" ")
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect();
+        Self::with_blinker(input, StoneBlinker::new())
+    }
 
-        let mut freqs = HashMap::new();
-        for stone in stones {
-            *freqs.entry(stone).or_insert(usize::default()) += 1;
-        }
+    /// Same as [`Stones::new`], reporting the first token that isn't a number.
+    pub fn try_new(input: &str) -> Result<Self, ParseError> {
+        Ok(Self::from_stones(parse_stones(input)?, StoneBlinker::new()))
+    }
 
-        Self {
-            freqs,
-            blinker: StoneBlinker::new(),
-        }
+    /// Same as [`Stones::new`], reusing `blinker` and whatever it has cached already.
+    pub fn with_blinker(input: &str, blinker: StoneBlinker) -> Self {
+        Self::from_stones(parse_stones(input).unwrap(), blinker)
+    }
+
+    fn from_stones(stones: Vec<usize>, blinker: StoneBlinker) -> Self {
+        let mut this = Self {
+            freqs: HashMap::new(),
+            blinker,
+        };
+        this.extend(stones);
+        this
     }
 
     /// Simulates the transformation of all stones for one blink.
+    ///
+    /// # Panics
+    /// If a stone overflows, see [`Stones::try_blinks`].
     pub fn blinks(&mut self) {
+        self.try_blinks().unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Same as [`Stones::blinks`], failing if a stone grows past `usize::MAX` or the number of
+    /// stones past `u64::MAX`. The stones are left unchanged on error.
+    pub fn try_blinks(&mut self) -> Result<(), OverflowError> {
         let mut new_freq = HashMap::new();
-        for (stone, count) in self.freqs.drain() {
-            let stones_after_blink = self.blinker.get(stone);
+        // Every count is part of the total, so none of them overflows if the total doesn't.
+        let mut total: u64 = 0;
+        for (&stone, &count) in &self.freqs {
+            let stones_after_blink = self.blinker.get(stone)?;
             for s in stones_after_blink.into_iter() {
+                total = total.checked_add(count).ok_or(OverflowError::Count)?;
                 *new_freq.entry(s).or_insert(0) += count;
             }
         }
 
-        self.freqs.extend(new_freq);
+        self.freqs = new_freq;
+        Ok(())
     }
 
     /// Repeats the blink simulation for a given number of iterations.
     ///
     /// # Returns
     /// The total number of stones after the specified number of blinks.
-    pub fn repeat(&mut self, blinks: usize) -> usize {
+    pub fn repeat(&mut self, blinks: usize) -> u64 {
         (0..blinks).for_each(|_| self.blinks());
         self.len()
     }
 
+    /// Same as [`Stones::repeat`], but records the number of stones along the way.
+    ///
+    /// # Returns
+    /// `blinks + 1` counts: the initial number of stones followed by the number after each blink.
+    pub fn repeat_tracked(&mut self, blinks: usize) -> Vec<u64> {
+        let mut history = Vec::with_capacity(blinks + 1);
+        history.push(self.len());
+        for _ in 0..blinks {
+            self.blinks();
+            history.push(self.len());
+        }
+
+        history
+    }
+
+    /// Counts the stones after `blinks` blinks without changing the current stones.
+    ///
+    /// Unlike [`Stones::repeat`], each distinct stone is expanded depth-first through a memo
+    /// shared across calls, see [`StoneBlinker::count_after`].
+    ///
+    /// # Panics
+    /// If a stone or the number of stones overflows, see [`Stones::try_count_after`].
+    pub fn count_after(&mut self, blinks: usize) -> u64 {
+        self.try_count_after(blinks)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Same as [`Stones::count_after`], failing if a stone grows past `usize::MAX` or the
+    /// number of stones past `u64::MAX`.
+    pub fn try_count_after(&mut self, blinks: usize) -> Result<u64, OverflowError> {
+        let stones: Vec<(usize, u64)> = self.freqs.iter().map(|(&s, &c)| (s, c)).collect();
+        stones.into_iter().try_fold(0u64, |total, (stone, count)| {
+            self.blinker
+                .count_after(stone, blinks)?
+                .checked_mul(count)
+                .and_then(|after| total.checked_add(after))
+                .ok_or(OverflowError::Count)
+        })
+    }
+
+    /// A digest of the current stones that doesn't depend on hash map iteration order.
+    ///
+    /// The `(stone, count)` pairs are folded in sorted order with 64-bit FNV-1a, so the same
+    /// stones always give the same digest, across runs and platforms.
+    pub fn digest(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        let mut pairs: Vec<(usize, u64)> = self.freqs.iter().map(|(&s, &c)| (s, c)).collect();
+        pairs.sort_unstable();
+
+        pairs
+            .into_iter()
+            .flat_map(|(stone, count)| {
+                let stone = stone as u64;
+                stone.to_le_bytes().into_iter().chain(count.to_le_bytes())
+            })
+            .fold(OFFSET, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(PRIME)
+            })
+    }
+
     #[inline]
-    pub fn len(&self) -> usize {
+    pub fn len(&self) -> u64 {
         self.freqs.values().sum()
     }
 
@@ -114,3 +350,177 @@ impl Stones {
         self.len() == 0
     }
 }
+
+impl Extend<usize> for Stones {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for stone in iter {
+            *self.freqs.entry(stone).or_default() += 1;
+        }
+    }
+}
+
+impl FromIterator<usize> for Stones {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        Self::from_stones(iter.into_iter().collect(), StoneBlinker::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "125 17";
+
+    #[test]
+    fn test_count_after() {
+        let mut stones = Stones::new(SAMPLE);
+        assert_eq!(stones.count_after(6), 22);
+        assert_eq!(stones.count_after(25), 55312);
+        assert_eq!(stones.repeat(25), 55312);
+
+        // Only a few hundred distinct (stone, depth) pairs stand in for 55312 stones.
+        assert!(stones.blinker.counts.len() < 1000);
+    }
+
+    #[test]
+    fn test_blink_at() {
+        assert_eq!(StoneBlinker::blink_at(0), Ok(vec![1]));
+        assert_eq!(StoneBlinker::blink_at(1000), Ok(vec![10, 0]));
+        assert_eq!(StoneBlinker::blink_at(99), Ok(vec![9, 9]));
+        assert_eq!(StoneBlinker::blink_at(1), Ok(vec![2024]));
+    }
+
+    #[test]
+    fn test_with_blinker() {
+        let mut blinker = StoneBlinker::new();
+        assert_eq!(blinker.get(17), Ok(vec![1, 7]));
+
+        let mut stones = Stones::with_blinker(SAMPLE, blinker);
+        assert_eq!(stones.repeat(25), 55312);
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(StoneBlinker::split(String::from("1000")), (10, 0));
+        assert_eq!(StoneBlinker::split(String::from("2007")), (20, 7));
+        assert_eq!(StoneBlinker::split(String::from("9900")), (99, 0));
+        assert_eq!(StoneBlinker::split(String::from("10")), (1, 0));
+    }
+
+    #[test]
+    fn test_stone_line() {
+        assert_eq!(
+            StoneLine::from("0 1 10 99 999").blink().stones(),
+            [1, 2024, 1, 0, 9, 9, 2021976]
+        );
+
+        let mut line = StoneLine::from(SAMPLE);
+        (0..6).for_each(|_| {
+            line.blink();
+        });
+        assert_eq!(line.stones().len(), 22);
+        assert_eq!(&line.stones()[..4], [2097446912, 14168, 4048, 2]);
+    }
+
+    #[test]
+    fn test_repeat_tracked() {
+        let history = Stones::new(SAMPLE).repeat_tracked(25);
+
+        assert_eq!(history.len(), 26);
+        assert_eq!(history[..7], [2, 3, 4, 5, 9, 13, 22]);
+        assert!(history.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(history[25], Stones::new(SAMPLE).repeat(25));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let mut stones = Stones::try_new("125  17\n").unwrap();
+        assert_eq!(stones.len(), 2);
+        assert_eq!(stones.repeat(25), 55312);
+
+        let error = ParseError {
+            token: "1x".to_string(),
+        };
+        assert_eq!(Stones::try_new("125 1x 17").err(), Some(error));
+    }
+
+    #[test]
+    fn test_with_factor() {
+        let mut blinker = StoneBlinker::with_factor(3);
+        assert_eq!(blinker.get(7), Ok(vec![21]));
+        assert_eq!(blinker.get(1), Ok(vec![3]));
+        assert_eq!(blinker.get(0), Ok(vec![1]));
+
+        let mut stones = Stones::with_blinker("7", StoneBlinker::with_factor(3));
+        // 7 -> 21 -> 2 1 -> 6 3
+        assert_eq!(stones.repeat(3), 2);
+
+        let mut stones = Stones::with_blinker(SAMPLE, StoneBlinker::with_factor(2024));
+        assert_eq!(stones.repeat(25), 55312);
+    }
+
+    #[test]
+    fn test_collect_and_extend() {
+        let mut stones: Stones = [0, 0, 1, 10].into_iter().collect();
+        assert_eq!(stones.len(), 4);
+        assert_eq!(stones.freqs[&0], 2);
+
+        stones.extend([10, 7]);
+        assert_eq!(stones.len(), 6);
+        assert_eq!(stones.freqs[&10], 2);
+
+        let mut stones: Stones = [125, 17].into_iter().collect();
+        assert_eq!(stones.repeat(25), 55312);
+    }
+
+    #[test]
+    fn test_digest() {
+        let mut first = Stones::new(SAMPLE);
+        first.repeat(25);
+        let mut second = Stones::new("17 125");
+        second.repeat(25);
+        assert_eq!(first.digest(), second.digest());
+
+        second.blinks();
+        assert_ne!(first.digest(), second.digest());
+    }
+
+    #[test]
+    fn test_counts_beyond_u32() {
+        let mut stones = Stones::new(SAMPLE);
+        let counted = stones.count_after(75);
+        assert_eq!(counted, 65601038650482);
+        assert!(counted > u32::MAX as u64);
+        assert_eq!(stones.repeat(75), counted);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_blink_overflow() {
+        // 19 digits, so it's multiplied rather than split.
+        let stone = 1_000_000_000_000_000_000;
+        let error = OverflowError::Stone {
+            stone,
+            factor: 2024,
+        };
+        assert_eq!(StoneBlinker::blink_at(stone), Err(error));
+
+        let mut stones: Stones = [1, stone].into_iter().collect();
+        assert!(stones.try_blinks().is_err());
+        assert_eq!(stones.len(), 2);
+    }
+
+    #[test]
+    fn test_count_overflow() {
+        // `10` splits into `1` and `0`, doubling the number of stones.
+        let mut stones: Stones = [10].into_iter().collect();
+        stones.freqs.insert(10, u64::MAX / 2 + 1);
+
+        assert_eq!(stones.try_blinks(), Err(OverflowError::Count));
+        assert_eq!(stones.try_count_after(1), Err(OverflowError::Count));
+        assert_eq!(stones.len(), u64::MAX / 2 + 1);
+
+        stones.freqs.insert(10, u64::MAX / 2);
+        assert_eq!(stones.try_count_after(1), Ok(u64::MAX - 1));
+    }
+}
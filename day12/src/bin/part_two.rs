@@ -1,11 +1,11 @@
 use day12::Garden;
-use util::read_file_to_string;
+use util::{print_solution, read_input};
 
 fn main() {
-    let input = read_file_to_string("input/12.txt").unwrap();
+    let input = read_input("input/12.txt");
     let garden = Garden::from(input.as_str());
 
     let total_price = garden.total_price(true);
 
-    println!("* Solution: {total_price} *");
+    print_solution(2, total_price);
 }
@@ -1,6 +1,8 @@
 use std::{
-    collections::{HashSet, VecDeque},
-    convert, fmt, ops,
+    collections::HashSet,
+    convert, fmt,
+    io::{self, BufRead},
+    ops,
 };
 
 enum Direction {
@@ -8,6 +10,10 @@ enum Direction {
     South,
     East,
     West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
 }
 
 impl Direction {
@@ -17,6 +23,10 @@ impl Direction {
             Direction::South => (1, 0),
             Direction::East => (0, 1),
             Direction::West => (0, -1),
+            Direction::NorthEast => (-1, 1),
+            Direction::NorthWest => (-1, -1),
+            Direction::SouthEast => (1, 1),
+            Direction::SouthWest => (1, -1),
         }
     }
 
@@ -41,16 +51,69 @@ struct Location {
 
 #[derive(Debug)]
 struct Region {
-    plots: Vec<Location>,
+    plant: char,
+    plots: HashSet<Location>,
     perimeter: usize,
     sides: usize,
 }
 
+/// Error returned when a garden map can't be parsed.
+#[derive(Debug)]
+pub enum ParseError {
+    /// A row whose length differs from the first row's. `line` is 1-based.
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The garden has no rows.
+    Empty,
+    /// Reading the input failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {} has {} plots, expected {}",
+                line, found, expected
+            ),
+            ParseError::Empty => write!(f, "garden is empty"),
+            ParseError::Io(err) => write!(f, "failed to read garden: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
+
+/// Measurements of a single region, see [`Garden::region_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionInfo {
+    pub plant: char,
+    pub area: usize,
+    pub perimeter: usize,
+    pub sides: usize,
+    pub price: usize,
+}
+
 pub struct Garden {
     plants: Vec<Vec<char>>,
     height: usize,
     width: usize,
     direction: [Direction; 4],
+    diagonals: [Direction; 4],
 }
 
 impl fmt::Debug for Location {
@@ -85,39 +148,39 @@ impl Region {
         self.plots.len()
     }
 
+    #[inline]
+    fn contains(&self, loc: Option<Location>) -> bool {
+        loc.is_some_and(|loc| self.plots.contains(&loc))
+    }
+
     /// Computes the number of contiguous sides (fence sections) in the region.
+    ///
+    /// Every plot edge facing out of the region is walked sideways, by the delta rotated a
+    /// quarter turn, to the last plot along the same fence. Edges of one side all end up at
+    /// the same plot, so the distinct `(end, delta)` pairs are the sides. Neighbors off the
+    /// grid (negative coordinates) are `None`, i.e. outside of the region.
     fn compute_sides(&mut self) {
         let mut unique_sides = HashSet::new();
 
         // For each plot in the region
-        for &Location { x, y } in &self.plots {
+        for &plot in &self.plots {
             // Check all four directions (North, East, South, West)
             for (dx, dy) in Direction::delta_all() {
-                let neighbor_x = x.wrapping_add(dx as usize);
-                let neighbor_y = y.wrapping_add(dy as usize);
-
                 // If the neighbor is not part of the region, track this side
-                if !self.plots.contains(&Location {
-                    x: neighbor_x,
-                    y: neighbor_y,
-                }) {
-                    let mut edge_x = x;
-                    let mut edge_y = y;
-
-                    // Traverse along the direction to find the end of the contiguous edge
-                    while self.plots.contains(&Location {
-                        x: edge_x.wrapping_add(dy as usize),
-                        y: edge_y.wrapping_add(dx as usize),
-                    }) && !self.plots.contains(&Location {
-                        x: edge_x.wrapping_add(dx as usize),
-                        y: edge_y.wrapping_add(dy as usize),
-                    }) {
-                        edge_x = edge_x.wrapping_add(dy as usize);
-                        edge_y = edge_y.wrapping_add(dx as usize);
-                    }
+                if self.contains(plot.add_delta(dx, dy)) {
+                    continue;
+                }
 
-                    unique_sides.insert((edge_x, edge_y, dx, dy));
+                // Traverse along the fence to find the end of the contiguous edge
+                let mut edge = plot;
+                while let Some(next) = edge.add_delta(dy, dx) {
+                    if !self.contains(Some(next)) || self.contains(next.add_delta(dx, dy)) {
+                        break;
+                    }
+                    edge = next;
                 }
+
+                unique_sides.insert((edge, dx, dy));
             }
         }
 
@@ -125,6 +188,31 @@ impl Region {
         self.sides = unique_sides.len();
     }
 
+    /// Counts the region's sides as its corners: a polygon has as many sides as corners.
+    ///
+    /// Each plot is checked on its four corners. A corner is convex when both plots beside
+    /// it are outside the region, and concave when both are inside but the diagonal one isn't.
+    /// Kept alongside [`Region::compute_sides`] to cross-check it.
+    #[cfg(test)]
+    fn compute_sides_corners(&self) -> usize {
+        let corners = [(-1, 0, 0, 1), (0, 1, 1, 0), (1, 0, 0, -1), (0, -1, -1, 0)];
+
+        let mut count = 0;
+        for &plot in &self.plots {
+            for (ax, ay, bx, by) in corners {
+                let a = self.contains(plot.add_delta(ax, ay));
+                let b = self.contains(plot.add_delta(bx, by));
+                let diagonal = self.contains(plot.add_delta(ax + bx, ay + by));
+
+                if (!a && !b) || (a && b && !diagonal) {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
     #[inline]
     fn price(&self, discount: bool) -> usize {
         if discount {
@@ -148,16 +236,53 @@ impl Garden {
                 Direction::East,
                 Direction::West,
             ],
+            diagonals: [
+                Direction::NorthEast,
+                Direction::NorthWest,
+                Direction::SouthEast,
+                Direction::SouthWest,
+            ],
+        }
+    }
+
+    /// Reads a garden, one row of plants per line, ignoring surrounding whitespace and blank
+    /// lines. Every row must be as long as the first one.
+    pub fn try_from_reader<R: BufRead>(reader: R) -> Result<Self, ParseError> {
+        let mut plants: Vec<Vec<char>> = Vec::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let row: Vec<char> = line.trim().chars().collect();
+            if row.is_empty() {
+                continue;
+            }
+            if let Some(first) = plants.first() {
+                if row.len() != first.len() {
+                    return Err(ParseError::Ragged {
+                        line: idx + 1,
+                        expected: first.len(),
+                        found: row.len(),
+                    });
+                }
+            }
+            plants.push(row);
+        }
+
+        if plants.is_empty() {
+            return Err(ParseError::Empty);
         }
+
+        Ok(Self::new(plants))
     }
 
     fn in_bound(&self, loc: &Location) -> bool {
         loc.x < self.height && loc.y < self.width
     }
 
-    fn adjacents_to(&self, plant: char, at: &Location) -> Vec<Location> {
+    /// Neighbors of `at` growing the same `plant`, also looking diagonally if `diagonals`.
+    fn adjacents_to(&self, plant: char, at: &Location, diagonals: bool) -> Vec<Location> {
         let mut adjacents = Vec::new();
-        for dir in &self.direction {
+        let extra: &[Direction] = if diagonals { &self.diagonals } else { &[] };
+        for dir in self.direction.iter().chain(extra) {
             let (delta_x, delta_y) = dir.delta();
             if let Some(adj) = at.add_delta(delta_x, delta_y) {
                 if self.in_bound(&adj) && self[&adj] == plant {
@@ -169,30 +294,32 @@ impl Garden {
         adjacents
     }
 
-    fn find_region(&self, start: Location, seen: &mut HashSet<Location>) -> Region {
-        let mut queue = VecDeque::from([start]);
-        let mut plots = Vec::new();
-        let mut perimeter = 0;
-        let sides = 0;
+    /// Flood fills the region containing `start`, breadth-first.
+    ///
+    /// Plots are kept in a set, so the result doesn't depend on the visiting order; regions
+    /// themselves are found in row-major order of their first plot by [`Garden::regions`].
+    fn find_region(
+        &self,
+        start: Location,
+        seen: &mut HashSet<Location>,
+        diagonals: bool,
+    ) -> Region {
         let target_plant = self[&start];
-
-        while let Some(location) = queue.pop_back() {
-            if seen.contains(&location) {
-                continue;
-            } else {
-                // Mark this plant as seen.
-                seen.insert(location);
-            }
-
-            plots.push(location);
-
-            let adjacents = self.adjacents_to(target_plant, &location);
-
-            perimeter += 4 - adjacents.len();
-            queue.extend(adjacents);
-        }
+        let plots = util::flood_fill(start, |location| {
+            self.adjacents_to(target_plant, &location, diagonals)
+        });
+        seen.extend(&plots);
+
+        // Fences only run between plots sharing an edge, even when regions also connect
+        // diagonally.
+        let perimeter = plots
+            .iter()
+            .map(|location| 4 - self.adjacents_to(target_plant, location, false).len())
+            .sum();
+        let sides = 0;
 
         let mut region = Region {
+            plant: target_plant,
             plots,
             perimeter,
             sides,
@@ -203,18 +330,51 @@ impl Garden {
     }
 
     fn regions(&self) -> Vec<Region> {
+        self.regions_with(false)
+    }
+
+    /// Flood fills every region, connecting plots diagonally as well if `diagonals`.
+    fn regions_with(&self, diagonals: bool) -> Vec<Region> {
         let mut seen = HashSet::with_capacity(self.width * self.height);
         let mut regions = Vec::new();
         for x in 0..self.height {
             for y in 0..self.width {
                 if !seen.contains(&Location { x, y }) {
-                    regions.push(self.find_region(Location { x, y }, &mut seen));
+                    regions.push(self.find_region(Location { x, y }, &mut seen, diagonals));
                 }
             }
         }
         regions
     }
 
+    /// Labels every plot with the id of its region, an index into [`Garden::region_report`].
+    pub fn region_map(&self) -> Vec<Vec<usize>> {
+        let mut labels = vec![vec![0; self.width]; self.height];
+        for (id, region) in self.regions().iter().enumerate() {
+            for plot in &region.plots {
+                labels[plot.x][plot.y] = id;
+            }
+        }
+
+        labels
+    }
+
+    /// Measurements of every region, in the order their first plot appears row by row.
+    ///
+    /// `price` is computed with or without the bulk discount, like [`Garden::total_price`].
+    pub fn region_report(&self, with_discount: bool) -> Vec<RegionInfo> {
+        self.regions()
+            .into_iter()
+            .map(|r| RegionInfo {
+                plant: r.plant,
+                area: r.area(),
+                perimeter: r.perimeter,
+                sides: r.sides,
+                price: r.price(with_discount),
+            })
+            .collect()
+    }
+
     #[inline]
     pub fn total_price(&self, with_discount: bool) -> usize {
         self.regions()
@@ -222,16 +382,31 @@ impl Garden {
             .map(|r| r.price(with_discount))
             .sum()
     }
+
+    /// Both [`Garden::total_price`]s, without and with the discount, from a single flood fill.
+    pub fn total_prices(&self) -> (usize, usize) {
+        self.regions()
+            .iter()
+            .fold((0, 0), |(perimeter_price, side_price), r| {
+                (perimeter_price + r.price(false), side_price + r.price(true))
+            })
+    }
+
+    /// Same as [`Garden::total_price`], but plots of the same plant that only touch diagonally
+    /// also belong to the same region.
+    pub fn total_price_8(&self, with_discount: bool) -> usize {
+        self.regions_with(true)
+            .into_iter()
+            .map(|r| r.price(with_discount))
+            .sum()
+    }
 }
 
 impl convert::From<&str> for Garden {
+    /// # Panics
+    /// If the garden is invalid, see [`Garden::try_from_reader`].
     fn from(value: &str) -> Self {
-        let plants = value
-            .trim()
-            .lines()
-            .map(|line| line.trim().chars().collect())
-            .collect();
-        Self::new(plants)
+        Self::try_from_reader(value.as_bytes()).unwrap()
     }
 }
 
@@ -280,4 +455,177 @@ MMMISSJEEE
         let total_price = garden.total_price(true);
         assert_eq!(total_price, 1206);
     }
+
+    #[test]
+    fn test_large_region_sides() {
+        // A 100x100 field of `A` with a single `B` in the middle.
+        let mut plants = vec![vec!['A'; 100]; 100];
+        plants[50][50] = 'B';
+        let garden = Garden::new(plants);
+
+        let regions = garden.regions();
+        let field = regions.iter().find(|r| r.area() == 9999).unwrap();
+        assert_eq!(field.sides, 8);
+        assert_eq!(field.perimeter, 404);
+        assert_eq!(garden.total_price(true), 9999 * 8 + 4);
+    }
+
+    #[test]
+    fn test_region_report() {
+        let garden = Garden::from(SAMPLE);
+
+        let report = garden.region_report(false);
+        assert_eq!(report.len(), 11);
+        assert_eq!(report.iter().map(|r| r.price).sum::<usize>(), 1930);
+        assert_eq!(
+            report[0],
+            RegionInfo {
+                plant: 'R',
+                area: 12,
+                perimeter: 18,
+                sides: 10,
+                price: 216,
+            }
+        );
+
+        let report = garden.region_report(true);
+        assert_eq!(report.iter().map(|r| r.price).sum::<usize>(), 1206);
+    }
+
+    #[test]
+    fn test_sides_touching_grid_edge() {
+        // The `A` region runs along the top and left edges of the grid.
+        let garden = Garden::from(
+            r"AAA
+ABB
+ABB",
+        );
+
+        let report = garden.region_report(true);
+        assert_eq!(report[0].plant, 'A');
+        assert_eq!((report[0].area, report[0].sides), (5, 6));
+        assert_eq!((report[1].area, report[1].sides), (4, 4));
+        assert_eq!(garden.total_price(true), 5 * 6 + 4 * 4);
+    }
+
+    #[test]
+    fn test_try_from_reader() {
+        let result = Garden::try_from_reader("AAA\nAB\nAAA".as_bytes());
+        assert!(matches!(
+            result,
+            Err(ParseError::Ragged {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            Garden::try_from_reader("\n".as_bytes()),
+            Err(ParseError::Empty)
+        ));
+
+        let garden = Garden::try_from_reader(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(garden.total_price(false), 1930);
+    }
+
+    #[test]
+    fn test_total_price_8() {
+        let garden = Garden::from(
+            r"AB
+BA",
+        );
+
+        // Four single plots, or two diagonal pairs.
+        assert_eq!(garden.regions().len(), 4);
+        assert_eq!(garden.regions_with(true).len(), 2);
+        assert_eq!(garden.total_price(false), 4 * 4);
+        assert_eq!(garden.total_price_8(false), 2 * (2 * 8));
+        assert_eq!(garden.total_price_8(true), 2 * (2 * 8));
+
+        let garden = Garden::from(SAMPLE);
+        assert_eq!(garden.total_price(false), 1930);
+        assert_eq!(garden.total_price(true), 1206);
+    }
+
+    #[test]
+    fn test_region_map() {
+        let garden = Garden::from(SAMPLE);
+        let labels = garden.region_map();
+
+        assert_eq!((labels.len(), labels[0].len()), (10, 10));
+        // Both in the `R` region.
+        assert_eq!(labels[0][0], labels[3][2]);
+        // `R` and the neighbouring `I`.
+        assert_ne!(labels[0][3], labels[0][4]);
+        // The two `I` regions.
+        assert_ne!(labels[0][4], labels[7][1]);
+
+        let report = garden.region_report(false);
+        assert_eq!(report[labels[0][0]].plant, 'R');
+        assert_eq!(labels.iter().flatten().max(), Some(&(report.len() - 1)));
+    }
+
+    #[test]
+    fn test_region_areas() {
+        let garden = Garden::from(SAMPLE);
+
+        let areas: Vec<(char, usize)> = garden
+            .region_report(false)
+            .iter()
+            .map(|r| (r.plant, r.area))
+            .collect();
+        assert_eq!(
+            areas,
+            vec![
+                ('R', 12),
+                ('I', 4),
+                ('C', 14),
+                ('F', 10),
+                ('V', 13),
+                ('J', 11),
+                ('C', 1),
+                ('E', 13),
+                ('I', 14),
+                ('M', 5),
+                ('S', 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compute_sides_corners() {
+        let e_shaped = r"EEEEE
+EXXXX
+EEEEE
+EXXXX
+EEEEE";
+        let mobius = r"AAAAAA
+AAABBA
+AAABBA
+ABBAAA
+ABBAAA
+AAAAAA";
+
+        for (input, expected) in [(SAMPLE, 1206), (e_shaped, 236), (mobius, 368)] {
+            let regions = Garden::from(input).regions();
+            assert!(regions.iter().all(|r| r.compute_sides_corners() == r.sides));
+
+            let price: usize = regions
+                .iter()
+                .map(|r| r.area() * r.compute_sides_corners())
+                .sum();
+            assert_eq!(price, expected);
+        }
+    }
+
+    #[test]
+    fn test_total_prices() {
+        let garden = Garden::from(SAMPLE);
+
+        assert_eq!(garden.total_prices(), (1930, 1206));
+        assert_eq!(
+            garden.total_prices(),
+            (garden.total_price(false), garden.total_price(true))
+        );
+    }
 }
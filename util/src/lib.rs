@@ -1,15 +1,34 @@
 use std::{
-    fs,
-    io::{self, Read},
+    collections::{HashSet, VecDeque},
+    fmt, fs,
+    hash::Hash,
+    io::{self, BufRead, Read},
+    ops,
     path::Path,
+    process,
+    time::Instant,
 };
 
+/// Opens the file at `path` for buffered reading.
+///
+/// # Panics
+/// If the file can't be opened, see [`try_read_file`].
 pub fn read_file(path: &str) -> io::BufReader<fs::File> {
-    let file_path = Path::new(&path);
-    let file = fs::File::open(file_path)
-        .unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e));
+    try_read_file(path).unwrap_or_else(|e| panic!("Failed to read file {}\n{}\n", path, e))
+}
+
+/// Same as [`read_file`], returning the error instead of panicking.
+pub fn try_read_file(path: &str) -> io::Result<io::BufReader<fs::File>> {
+    let file = fs::File::open(Path::new(path))?;
+    Ok(io::BufReader::new(file))
+}
 
-    io::BufReader::new(file)
+/// Reads a puzzle input for a bin, or prints why it couldn't and exits with status 1.
+pub fn read_input(file_path: &str) -> String {
+    read_file_to_string(file_path).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", file_path, e);
+        process::exit(1)
+    })
 }
 
 pub fn read_file_to_string(file_path: &str) -> io::Result<String> {
@@ -18,3 +37,562 @@ pub fn read_file_to_string(file_path: &str) -> io::Result<String> {
     file.read_to_string(&mut contents)?; // Read the file's contents into the String
     Ok(contents) // Return the String
 }
+
+/// Reads the file at `file_path` line by line.
+pub fn read_lines(file_path: &str) -> io::Result<Vec<String>> {
+    let file = fs::File::open(file_path)?;
+    io::BufReader::new(file).lines().collect()
+}
+
+/// Same as [`read_lines`], with trailing whitespace removed from every line.
+pub fn read_lines_trimmed(file_path: &str) -> io::Result<Vec<String>> {
+    let lines = read_lines(file_path)?;
+    Ok(lines
+        .into_iter()
+        .map(|line| line.trim_end().to_string())
+        .collect())
+}
+
+/// Wraps a string in a [`BufRead`], so functions reading from files can be fed inline samples.
+pub fn str_reader(s: &str) -> impl BufRead + '_ {
+    io::Cursor::new(s.as_bytes())
+}
+
+/// Collects every point reachable from `start` by repeatedly following `neighbors`, `start` included.
+pub fn flood_fill<P, F>(start: P, neighbors: F) -> HashSet<P>
+where
+    P: Hash + Eq + Copy,
+    F: Fn(P) -> Vec<P>,
+{
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(point) = queue.pop_front() {
+        for next in neighbors(point) {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    seen
+}
+
+/// A cell in a grid, `x` being the row and `y` the column.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl fmt::Debug for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(x: {}, y: {})", self.x, self.y)
+    }
+}
+
+impl Point {
+    pub fn new(x: usize, y: usize) -> Self {
+        Point { x, y }
+    }
+
+    /// Moves by `(dx, dy)`, or `None` if either coordinate would underflow or overflow.
+    pub fn delta(&self, dx: i8, dy: i8) -> Option<Self> {
+        Some(Point {
+            x: self.x.checked_add_signed(dx as isize)?,
+            y: self.y.checked_add_signed(dy as isize)?,
+        })
+    }
+
+    /// Whether the point lies within a grid of `height` rows and `width` columns.
+    pub fn in_bounds(&self, height: usize, width: usize) -> bool {
+        self.x < height && self.y < width
+    }
+}
+
+/// A rectangular grid, indexed by row `x` then column `y` like [`Point`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    data: Vec<Vec<T>>,
+    height: usize,
+    width: usize,
+}
+
+impl<T> Grid<T> {
+    /// Wraps `data`, one `Vec` per row.
+    ///
+    /// # Panics
+    /// If the rows don't all have the same length.
+    pub fn new(data: Vec<Vec<T>>) -> Self {
+        let (height, width) = (data.len(), data.first().map_or(0, Vec::len));
+        assert!(
+            data.iter().all(|row| row.len() == width),
+            "Grid rows must all have the same length"
+        );
+
+        Self {
+            data,
+            height,
+            width,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.height && y < self.width
+    }
+
+    /// The cell at row `x` and column `y`, or `None` if it's out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.data.get(x)?.get(y)
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.data.get_mut(x)?.get_mut(y)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.data.iter().map(Vec::as_slice)
+    }
+
+    /// Every column, top to bottom.
+    pub fn cols(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |y| self.data.iter().map(move |row| &row[y]))
+    }
+}
+
+impl Grid<char> {
+    /// Parses a grid of characters, see [`parse_char_grid`].
+    ///
+    /// # Panics
+    /// If the lines don't all have the same length.
+    pub fn from_char_lines(input: &str) -> Self {
+        Self::new(parse_char_grid(input))
+    }
+}
+
+impl<T> ops::Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &Self::Output {
+        self.get(point.x, point.y).unwrap_or_else(|| {
+            panic!(
+                "Point {:?} out of bounds, grid's dimensions: (height: {}, width: {})",
+                point, self.height, self.width
+            )
+        })
+    }
+}
+
+/// A cardinal direction on a grid, with North pointing to the first row.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Every direction, clockwise from [`Direction::North`].
+    pub fn all() -> [Direction; 4] {
+        [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ]
+    }
+
+    /// Returns the `(dx, dy)` step for [`Point::delta`].
+    pub fn delta(&self) -> (i8, i8) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::East => (0, 1),
+            Direction::South => (1, 0),
+            Direction::West => (0, -1),
+        }
+    }
+
+    /// Rotates the direction 90 degrees clockwise.
+    pub fn rotate_cw(&self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    /// Rotates the direction 90 degrees counterclockwise.
+    pub fn rotate_ccw(&self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::West => Direction::South,
+            Direction::South => Direction::East,
+            Direction::East => Direction::North,
+        }
+    }
+}
+
+/// Parses the arrows `^`, `>`, `v` and `<`.
+impl TryFrom<char> for Direction {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '^' => Ok(Direction::North),
+            '>' => Ok(Direction::East),
+            'v' => Ok(Direction::South),
+            '<' => Ok(Direction::West),
+            _ => Err(format!("Invalid character for direction: {}", c)),
+        }
+    }
+}
+
+impl From<Direction> for char {
+    fn from(val: Direction) -> Self {
+        match val {
+            Direction::North => '^',
+            Direction::East => '>',
+            Direction::South => 'v',
+            Direction::West => '<',
+        }
+    }
+}
+
+/// Formats the solution banner for `part`, one star per part: `"** Solution: 42 **"`.
+pub fn format_solution(part: u8, value: impl fmt::Display) -> String {
+    let stars = "*".repeat(part as usize);
+    format!("{stars} Solution: {value} {stars}")
+}
+
+/// Prints the solution banner for `part`, see [`format_solution`].
+pub fn print_solution(part: u8, value: impl fmt::Display) {
+    println!("{}", format_solution(part, value));
+}
+
+/// Runs `f`, printing how long it took to stderr as `"{label}: {elapsed:?}"`.
+pub fn timed<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    eprintln!("{}: {:?}", label, start.elapsed());
+    result
+}
+
+/// Splits `input` into sections separated by one or more blank lines.
+///
+/// Blank lines may contain whitespace. Sections don't include the line breaks around them.
+pub fn split_sections(input: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let (mut start, mut end, mut offset) = (None, 0, 0);
+
+    for line in input.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if content.trim().is_empty() {
+            if let Some(start) = start.take() {
+                sections.push(&input[start..end]);
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + content.len();
+        }
+        offset += line.len();
+    }
+
+    if let Some(start) = start {
+        sections.push(&input[start..end]);
+    }
+
+    sections
+}
+
+/// Splits an input made of exactly two sections, see [`split_sections`].
+pub fn split_two_sections(input: &str) -> Option<(&str, &str)> {
+    match split_sections(input)[..] {
+        [first, second] => Some((first, second)),
+        _ => None,
+    }
+}
+
+/// Parses a grid of characters, one row per line, ignoring trailing whitespace.
+pub fn parse_char_grid(input: &str) -> Vec<Vec<char>> {
+    input
+        .trim_end()
+        .lines()
+        .map(|line| line.trim_end().chars().collect())
+        .collect()
+}
+
+/// Reads the file at `file_path` as a grid of characters, see [`parse_char_grid`].
+pub fn read_char_grid(file_path: &str) -> io::Result<Vec<Vec<char>>> {
+    read_file_to_string(file_path).map(|contents| parse_char_grid(&contents))
+}
+
+/// Parses a grid of single digits, one row per line, ignoring trailing whitespace.
+///
+/// # Panics
+/// If the grid contains anything but digits, see [`parse_digit_grid_or`].
+pub fn parse_digit_grid(input: &str) -> Vec<Vec<u8>> {
+    parse_char_grid(input)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|c| match c.to_digit(10) {
+                    Some(digit) => digit as u8,
+                    None => panic!("Invalid digit {:?} in grid", c),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Same as [`parse_digit_grid`], using `fallback` for every cell that isn't a digit.
+pub fn parse_digit_grid_or(input: &str, fallback: u8) -> Vec<Vec<u8>> {
+    parse_char_grid(input)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|c| c.to_digit(10).map_or(fallback, |digit| digit as u8))
+                .collect()
+        })
+        .collect()
+}
+
+/// Swaps a grid's rows and columns.
+///
+/// # Panics
+/// If the rows don't all have the same length.
+pub fn transpose<T: Clone>(grid: &[Vec<T>]) -> Vec<Vec<T>> {
+    let width = grid.first().map_or(0, Vec::len);
+    assert!(
+        grid.iter().all(|row| row.len() == width),
+        "Can't transpose a grid with rows of different lengths"
+    );
+
+    (0..width)
+        .map(|col| grid.iter().map(|row| row[col].clone()).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a file in the temp directory, named after the calling test.
+    fn temp_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("aoc_util_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_parse_char_grid() {
+        let grid = parse_char_grid("#..#\n.##.\r\n#..#\n");
+
+        assert_eq!(grid.len(), 3);
+        assert!(grid.iter().all(|row| row.len() == 4));
+        assert_eq!(grid[1], vec!['.', '#', '#', '.']);
+    }
+
+    #[test]
+    fn test_parse_digit_grid() {
+        assert_eq!(
+            parse_digit_grid("123\n456"),
+            vec![vec![1, 2, 3], vec![4, 5, 6]]
+        );
+        assert_eq!(
+            parse_digit_grid_or("1.3\n45\n", u8::MAX),
+            vec![vec![1, u8::MAX, 3], vec![4, 5]]
+        );
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let path = temp_file("read_lines", "first  \nsecond\r\n\nlast");
+
+        assert_eq!(
+            read_lines(&path).unwrap(),
+            vec!["first  ", "second", "", "last"]
+        );
+        assert_eq!(
+            read_lines_trimmed(&path).unwrap(),
+            vec!["first", "second", "", "last"]
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = temp_file("read_file", "3   4\n4   3\n");
+
+        let lines: Vec<String> = read_file(&path).lines().map_while(Result::ok).collect();
+        assert_eq!(lines, vec!["3   4", "4   3"]);
+        assert_eq!(read_file_to_string(&path).unwrap(), "3   4\n4   3\n");
+    }
+
+    #[test]
+    fn test_try_read_file_missing() {
+        let path = std::env::temp_dir().join("aoc_util_does_not_exist.txt");
+
+        let result = try_read_file(&path.to_string_lossy());
+        assert_eq!(
+            result.err().map(|e| e.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let input = "\na: 1\nb: 2\n\nc\r\n\n  \n\nd\ne\n";
+
+        assert_eq!(split_sections(input), vec!["a: 1\nb: 2", "c", "d\ne"]);
+        assert_eq!(split_two_sections(input), None);
+        assert_eq!(split_two_sections("1|2\n\n1,2\n"), Some(("1|2", "1,2")));
+    }
+
+    #[test]
+    fn test_timed() {
+        assert_eq!(timed("sum", || (1..=10).sum::<u32>()), 55);
+        assert_eq!(timed("owned", || String::from("value")), "value");
+    }
+
+    #[test]
+    fn test_transpose() {
+        let grid = vec![vec![1, 2, 3], vec![4, 5, 6]];
+
+        assert_eq!(transpose(&grid), vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+        assert_eq!(transpose(&transpose(&grid)), grid);
+        assert!(transpose::<char>(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_transpose_ragged() {
+        transpose(&[vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn test_str_reader() {
+        let lines: Vec<String> = str_reader("first\nsecond\n\nlast\n")
+            .lines()
+            .map_while(Result::ok)
+            .collect();
+
+        assert_eq!(lines, vec!["first", "second", "", "last"]);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let grid = parse_char_grid("AAB\nBAB\nBAA\n");
+        let neighbors = |(r, c): (usize, usize)| {
+            [(0, 1), (2, 1), (1, 0), (1, 2)]
+                .into_iter()
+                .filter_map(|(dr, dc)| Some(((r + dr).checked_sub(1)?, (c + dc).checked_sub(1)?)))
+                .filter(|&(r, c)| grid.get(r).and_then(|row| row.get(c)) == Some(&'A'))
+                .collect()
+        };
+
+        let region = flood_fill((0, 0), neighbors);
+
+        assert_eq!(
+            region,
+            HashSet::from([(0, 0), (0, 1), (1, 1), (2, 1), (2, 2)])
+        );
+        assert_eq!(flood_fill((1, 0), |_| vec![]), HashSet::from([(1, 0)]));
+    }
+
+    #[test]
+    fn test_format_solution() {
+        assert_eq!(format_solution(1, 311), "* Solution: 311 *");
+        assert_eq!(format_solution(2, "1115"), "** Solution: 1115 **");
+    }
+
+    #[test]
+    fn test_point_delta() {
+        let origin = Point::new(0, 0);
+        assert_eq!(origin.delta(-1, 0), None);
+        assert_eq!(origin.delta(0, -1), None);
+        assert_eq!(origin.delta(1, 2), Some(Point::new(1, 2)));
+        assert_eq!(Point::new(3, 3).delta(-3, -1), Some(Point::new(0, 2)));
+        assert_eq!(Point::new(usize::MAX, 0).delta(1, 0), None);
+    }
+
+    #[test]
+    fn test_point_in_bounds() {
+        assert!(Point::new(1, 2).in_bounds(2, 3));
+        assert!(!Point::new(2, 0).in_bounds(2, 3));
+        assert!(!Point::new(0, 3).in_bounds(2, 3));
+    }
+
+    #[test]
+    fn test_direction_rotate_cw_cycles() {
+        let mut dir = Direction::North;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            seen.push(dir);
+            assert_eq!(dir.rotate_cw().rotate_ccw(), dir);
+            dir = dir.rotate_cw();
+        }
+
+        assert_eq!(dir, Direction::North);
+        assert_eq!(seen, Direction::all());
+    }
+
+    #[test]
+    fn test_direction_deltas() {
+        for dir in Direction::all() {
+            let (dx, dy) = dir.delta();
+            let (ox, oy) = dir.rotate_cw().rotate_cw().delta();
+            assert_eq!((dx + ox, dy + oy), (0, 0));
+            assert_eq!(dir.rotate_cw().delta(), (dy, -dx));
+        }
+
+        let center = Point::new(1, 1);
+        assert_eq!(center.delta(-1, 0), Some(Point::new(0, 1)));
+        assert_eq!(
+            Direction::all().map(|dir| {
+                let (dx, dy) = dir.delta();
+                center.delta(dx, dy).unwrap()
+            }),
+            [
+                Point::new(0, 1),
+                Point::new(1, 2),
+                Point::new(2, 1),
+                Point::new(1, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid_get_and_index() {
+        let grid = Grid::from_char_lines("abc\ndef\n");
+
+        assert_eq!((grid.height(), grid.width()), (2, 3));
+        assert_eq!(grid.get(1, 2), Some(&'f'));
+        assert_eq!(grid[Point::new(0, 1)], 'b');
+        assert!(grid.in_bounds(1, 2));
+
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert!(!grid.in_bounds(0, 3));
+    }
+
+    #[test]
+    fn test_grid_rows_and_cols() {
+        let grid = Grid::new(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+
+        let rows: Vec<&[u8]> = grid.rows().collect();
+        assert_eq!(rows, vec![&[1, 2, 3][..], &[4, 5, 6][..]]);
+
+        let cols: Vec<Vec<u8>> = grid.cols().map(|col| col.copied().collect()).collect();
+        assert_eq!(cols, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+    }
+}